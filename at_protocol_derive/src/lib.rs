@@ -0,0 +1,395 @@
+//! Derive macros for the `at_protocol` crate. See `at_protocol::AtCommand` and `at_protocol::AtResponse`.
+
+extern crate proc_macro;
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitInt, LitStr, Type};
+
+/// Implement `Command` for a struct.
+///
+/// The struct needs exactly one of these attributes, which also sets the form of the command:
+/// - `#[at(execute = "+RST")]` sends `AT+RST`
+/// - `#[at(query = "+CWMODE")]` sends `AT+CWMODE?`
+/// - `#[at(set = "+CWMODE")]` sends `AT+CWMODE=<fields>`, with each field written as an `Argument`
+/// - `#[at(test = "+CWMODE")]` sends `AT+CWMODE=?`
+///
+/// Only set commands can have fields. A field marked `#[at(redact)]` is written as `"***"` in the logs.
+/// A `Persistence` field marked `#[at(persistence)]` is not an argument, but adds `_CUR` or `_DEF` to the name of the command.
+///
+/// The command outputs `()`, unless `#[at(response = "+CWMODE", output = Type)]` is given. Then the first `+CWMODE:` line of the response is parsed into `Type`, which implements `FromFields`, and the fields of `Type` marked `#[at(redact)]` are written as `"***"` in the logs.
+/// `#[at(timeout_ms = 5000)]` sets the timeout of the command.
+///
+/// ```ignore
+/// #[derive(AtCommand)]
+/// #[at(set = "+CWMODE")]
+/// pub struct SetWifiMode(pub WifiMode);
+/// ```
+#[proc_macro_derive(AtCommand, attributes(at))]
+pub fn derive_at_command(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    at_command(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implement `FromFields` for a struct, so it can be the output of an `AtCommand`.
+///
+/// Each field is taken from the field of the response line at the same position. `Option` fields are `None` if the field is empty or missing.
+/// A field marked `#[at(redact)]` is written as `"***"` in the logs.
+#[proc_macro_derive(AtResponse, attributes(at))]
+pub fn derive_at_response(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    at_response(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Form {
+    Test,
+    Query,
+    Set,
+    Execute,
+}
+
+#[derive(Default)]
+struct CommandAttributes {
+    command: Option<(Form, LitStr)>,
+    response: Option<LitStr>,
+    output: Option<Type>,
+    timeout_ms: Option<LitInt>,
+}
+
+fn command_attributes(input: &DeriveInput) -> syn::Result<CommandAttributes> {
+    let mut result = CommandAttributes::default();
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("at")) {
+        attr.parse_nested_meta(|meta| {
+            let form = if meta.path.is_ident("test") {
+                Some(Form::Test)
+            } else if meta.path.is_ident("query") {
+                Some(Form::Query)
+            } else if meta.path.is_ident("set") {
+                Some(Form::Set)
+            } else if meta.path.is_ident("execute") {
+                Some(Form::Execute)
+            } else {
+                None
+            };
+
+            if let Some(form) = form {
+                if result.command.is_some() {
+                    return Err(
+                        meta.error("only one of `test`, `query`, `set` or `execute` can be given")
+                    );
+                }
+                result.command = Some((form, meta.value()?.parse()?));
+            } else if meta.path.is_ident("response") {
+                result.response = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("output") {
+                result.output = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("timeout_ms") {
+                result.timeout_ms = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("unknown attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(result)
+}
+
+#[derive(Default)]
+struct FieldAttributes {
+    /// `#[at(redact)]`
+    redact: bool,
+    /// `#[at(persistence)]`
+    persistence: bool,
+}
+
+fn field_attributes(field: &syn::Field) -> syn::Result<FieldAttributes> {
+    let mut result = FieldAttributes::default();
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("at")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("redact") {
+                result.redact = true;
+            } else if meta.path.is_ident("persistence") {
+                result.persistence = true;
+            } else {
+                return Err(meta.error("unknown attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(result)
+}
+
+fn at_command(input: DeriveInput) -> syn::Result<TokenStream> {
+    let attributes = command_attributes(&input)?;
+    let (form, name) = attributes.command.ok_or_else(|| {
+        syn::Error::new(
+            input.ident.span(),
+            "expected `#[at(test = \"..\")]`, `#[at(query = \"..\")]`, `#[at(set = \"..\")]` or `#[at(execute = \"..\")]`",
+        )
+    })?;
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "AtCommand can only be derived for structs",
+            ))
+        }
+    };
+    if form != Form::Set && !fields.is_empty() {
+        return Err(syn::Error::new(
+            fields.span(),
+            "only `set` commands have arguments",
+        ));
+    }
+
+    let prefix = match form {
+        Form::Test => format!("AT{}=?\r\n", name.value()),
+        Form::Query => format!("AT{}?\r\n", name.value()),
+        Form::Set => format!("AT{}", name.value()),
+        Form::Execute => format!("AT{}\r\n", name.value()),
+    };
+
+    let mut arguments = Vec::new();
+    let mut redacted_arguments = Vec::new();
+    let mut any_redacted = false;
+    let mut persistence = None;
+    for (index, field) in fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = syn::Index::from(index);
+                quote!(#index)
+            }
+        };
+        let field_attributes = field_attributes(field)?;
+        if field_attributes.persistence {
+            if persistence.is_some() {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "only one field can have `#[at(persistence)]`",
+                ));
+            }
+            persistence = Some(quote! {
+                output.write_str(::at_protocol::command::Persistence::suffix(self.#member))?;
+            });
+            continue;
+        }
+        let separator = if arguments.is_empty() {
+            quote!()
+        } else {
+            quote!(skipped += 1;)
+        };
+        arguments.push(quote! {
+            #separator
+            if ::at_protocol::command::Argument::is_present(&self.#member) {
+                for _ in 0..skipped {
+                    output.write_char(',')?;
+                }
+                skipped = 0;
+                ::at_protocol::command::Argument::write(&self.#member, output)?;
+            }
+        });
+        if field_attributes.redact {
+            any_redacted = true;
+            redacted_arguments.push(quote! {
+                #separator
+                if ::at_protocol::command::Argument::is_present(&self.#member) {
+                    for _ in 0..skipped {
+                        output.write_char(',')?;
+                    }
+                    skipped = 0;
+                    output.write_str("\"***\"")?;
+                }
+            });
+        } else {
+            redacted_arguments.push(arguments.last().unwrap().clone());
+        }
+    }
+
+    let encode_body = |arguments: &[TokenStream]| {
+        if form == Form::Set {
+            quote! {
+                use ::core::fmt::Write as _;
+                output.write_str(#prefix)?;
+                #persistence
+                output.write_char('=')?;
+                #[allow(unused_mut, unused_variables)]
+                let mut skipped = 0usize;
+                #(#arguments)*
+                output.write_str("\r\n")?;
+                ::core::result::Result::Ok(())
+            }
+        } else {
+            quote! {
+                output.write_str(#prefix).map_err(::core::convert::Into::into)
+            }
+        }
+    };
+    let encode = encode_body(&arguments);
+    let encode_redacted = if any_redacted {
+        let body = encode_body(&redacted_arguments);
+        quote! {
+            fn encode_redacted(&self, output: &mut impl ::core::fmt::Write) -> ::core::result::Result<(), ::at_protocol::Error> {
+                #body
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    let timeout = attributes.timeout_ms.map(|ms| {
+        quote! {
+            fn timeout(&self) -> ::core::option::Option<::core::time::Duration> {
+                ::core::option::Option::Some(::core::time::Duration::from_millis(#ms))
+            }
+        }
+    });
+
+    let (output, decode, redact_response) = match (attributes.response, attributes.output) {
+        (Some(response), Some(output)) => (
+            quote!(#output),
+            quote! {
+                let fields = ::at_protocol::command::parser::line(input, #response)?;
+                <#output as ::at_protocol::command::parser::FromFields>::from_fields(&fields)
+            },
+            quote! {
+                fn redact_response_line(&self, line: &str, output: &mut impl ::core::fmt::Write) -> ::core::result::Result<(), ::at_protocol::Error> {
+                    ::at_protocol::command::parser::redact_line(
+                        line,
+                        #response,
+                        <#output as ::at_protocol::command::parser::FromFields>::REDACTED,
+                        output,
+                    )
+                }
+            },
+        ),
+        (None, None) => (
+            quote!(()),
+            quote! {
+                let _ = input;
+                ::core::result::Result::Ok(())
+            },
+            quote!(),
+        ),
+        (Some(response), None) => {
+            return Err(syn::Error::new(
+                response.span(),
+                "`response` needs an `output` type",
+            ))
+        }
+        (None, Some(output)) => {
+            return Err(syn::Error::new(
+                output.span(),
+                "`output` needs a `response` line to parse",
+            ))
+        }
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::at_protocol::Command for #ident #ty_generics #where_clause {
+            type Output = #output;
+
+            fn encode(&self, output: &mut impl ::core::fmt::Write) -> ::core::result::Result<(), ::at_protocol::Error> {
+                #encode
+            }
+
+            #encode_redacted
+
+            fn decode(&self, input: &[u8]) -> ::core::result::Result<Self::Output, ::at_protocol::Error> {
+                #decode
+            }
+
+            #redact_response
+
+            #timeout
+        }
+    })
+}
+
+/// Whether the type is written as `Option<..>`.
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+fn at_response(input: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "AtResponse can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut redacted = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let field_attributes = field_attributes(field)?;
+        if field_attributes.persistence {
+            return Err(syn::Error::new(
+                field.span(),
+                "`#[at(persistence)]` is only supported by `AtCommand`",
+            ));
+        }
+        if field_attributes.redact {
+            redacted.push(index);
+        }
+    }
+    let redacted = if redacted.is_empty() {
+        quote!()
+    } else {
+        quote!(const REDACTED: &'static [usize] = &[#(#redacted),*];)
+    };
+
+    let values = fields.iter().enumerate().map(|(index, field)| {
+        let name = field
+            .ident
+            .as_ref()
+            .map_or_else(|| index.to_string(), ToString::to_string);
+        if is_option(&field.ty) {
+            quote!(fields.get_opt(#index, #name)?)
+        } else {
+            quote!(fields.get(#index, #name)?)
+        }
+    });
+    let body = match fields {
+        Fields::Named(named) => {
+            let names = named.named.iter().map(|field| &field.ident);
+            quote!(Self { #(#names: #values),* })
+        }
+        Fields::Unnamed(_) => quote!(Self(#(#values),*)),
+        Fields::Unit => quote!(Self),
+    };
+
+    let ident = &input.ident;
+    let lifetime = syn::Lifetime::new("'__at", Span::call_site());
+    let mut generics = input.generics.clone();
+    generics.params.insert(0, syn::parse_quote!(#lifetime));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::at_protocol::command::parser::FromFields<#lifetime> for #ident #ty_generics #where_clause {
+            #redacted
+
+            fn from_fields(fields: &::at_protocol::command::parser::Fields<#lifetime>) -> ::core::result::Result<Self, ::at_protocol::Error> {
+                ::core::result::Result::Ok(#body)
+            }
+        }
+    })
+}
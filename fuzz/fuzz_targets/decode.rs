@@ -0,0 +1,95 @@
+//! Passes arbitrary bytes to the decoder of every command, as the body of a response with each outcome. Decoding should never panic.
+
+#![no_main]
+
+use at_protocol::{command, Command, Outcome, Response};
+use libfuzzer_sys::fuzz_target;
+
+const OUTCOMES: [Outcome; 11] = [
+    Outcome::Ok,
+    Outcome::Error(None),
+    Outcome::Error(Some(0x0109_0000)),
+    Outcome::Fail,
+    Outcome::SendOk,
+    Outcome::SendFail,
+    Outcome::BusyProcessing,
+    Outcome::BusySending,
+    Outcome::CmeError(12),
+    Outcome::CmsError(500),
+    Outcome::Prompt,
+];
+
+fn decode(command: impl Command, data: &[u8]) {
+    let _ = command.decode(data);
+    for outcome in OUTCOMES.iter() {
+        let _ = command.decode_response(&Response {
+            body: data,
+            outcome: *outcome,
+        });
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    decode(command::Test, data);
+    decode(command::Restart, data);
+    decode(command::DisconnectFromAp, data);
+    decode(command::GetVersion, data);
+    decode(command::GetWifiMode, data);
+    decode(
+        command::SetWifiMode(
+            command::WifiMode::StationMode,
+            command::Persistence::Current,
+        ),
+        data,
+    );
+    decode(command::SetSysStore(true), data);
+    decode(command::ListAp, data);
+    decode(
+        command::FilteredListAp {
+            fields: command::ScanFields {
+                wps: false,
+                ..command::ScanFields::ALL
+            },
+            ..command::FilteredListAp::default()
+        },
+        data,
+    );
+    decode(command::ConnectToAp::new("ssid", "password"), data);
+    decode(command::GetConnectedAp, data);
+    decode(command::form::Query(command::Cwmode), data);
+    decode(command::form::Test(command::Cwmode), data);
+    decode(command::form::IsSupported("+CWMODE_CUR"), data);
+    decode(command::GetSoftAp, data);
+    decode(command::ListStations, data);
+    decode(command::KickStation(None), data);
+    decode(
+        command::GetDhcp {
+            persistence: command::Persistence::Default,
+            firmware: command::FirmwareFamily::EspAtV2,
+        },
+        data,
+    );
+    decode(
+        command::GetDhcp {
+            persistence: command::Persistence::Current,
+            firmware: command::FirmwareFamily::NonOs,
+        },
+        data,
+    );
+    decode(command::GetDhcpServer(command::Persistence::Default), data);
+    decode(
+        command::GetIpConfig {
+            interface: command::WifiMode::StationMode,
+            persistence: command::Persistence::Default,
+        },
+        data,
+    );
+    decode(
+        command::GetMacAddress {
+            interface: command::WifiMode::ApMode,
+            persistence: command::Persistence::Current,
+        },
+        data,
+    );
+    decode(command::GetLocalAddresses, data);
+});
@@ -0,0 +1,41 @@
+//! Feeds arbitrary bytes to an interface as if they were received from the module. Framing the response, parsing URCs and decoding should never panic.
+
+#![no_main]
+
+use at_protocol::{command, Error, Interface, Transport};
+use libfuzzer_sys::fuzz_target;
+use std::io::{self, Cursor, Read, Write};
+use std::time::Duration;
+
+struct Replay(Cursor<Vec<u8>>);
+
+impl Read for Replay {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for Replay {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Transport for Replay {
+    fn set_timeout(&mut self, _timeout: Duration) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let transport = Replay(Cursor::new(data.to_vec()));
+    let mut interface = Interface::with_transport(transport).unwrap();
+    interface.set_urc_handler(|_| {});
+    let _ = interface.send(command::ListAp);
+    let _ = interface.send(command::GetConnectedAp);
+    let _ = interface.send(command::GetVersion);
+});
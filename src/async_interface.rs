@@ -0,0 +1,177 @@
+use crate::framer::Frame;
+use crate::receiver::Receiver;
+use crate::{encode_command, Command, Error, RetryPolicy, Urc};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// An async version of [Interface](crate::Interface), for use with tokio.
+///
+/// This works with any `AsyncRead + AsyncWrite` stream, and sends the same [Command]s as the blocking interface.
+pub struct AsyncInterface<T> {
+    port: T,
+    timeout: Duration,
+    retry_policy: RetryPolicy,
+    receiver: Receiver<Vec<u8>>,
+}
+
+#[cfg(feature = "tokio-serial")]
+impl AsyncInterface<tokio_serial::SerialStream> {
+    /// Open the given serial port with the given baud rate.
+    pub fn open(port: &str, baud_rate: u32) -> Result<Self, Error> {
+        use tokio_serial::SerialPortBuilderExt;
+
+        let port = tokio_serial::new(port, baud_rate).open_native_async()?;
+        Ok(Self::new(port))
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> AsyncInterface<T> {
+    /// Create an interface that talks over the given stream, with a default timeout of 30 seconds.
+    pub fn new(port: T) -> Self {
+        Self {
+            port,
+            timeout: Duration::from_secs(30),
+            retry_policy: RetryPolicy::NONE,
+            receiver: Receiver::new(Vec::new()),
+        }
+    }
+
+    /// Set the callback that receives the [Urc]s sent by the module.
+    ///
+    /// URCs are only read while a command is being sent. They are not part of the response that is passed to the command.
+    pub fn set_urc_handler(&mut self, handler: impl FnMut(Urc) + Send + 'static) {
+        self.receiver.set_urc_handler(Box::new(handler));
+    }
+
+    /// Set how long a command may take if it does not have its own [timeout](Command::timeout).
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Set when failed commands are retried.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// Consume the interface, returning the underlying stream.
+    pub fn into_inner(self) -> T {
+        self.port
+    }
+
+    pub async fn send<C: Command>(&mut self, command: C) -> Result<C::Output, Error> {
+        let mut retries = 0;
+        loop {
+            match self.send_once(&command).await {
+                Err(e) if self.retry_policy.should_retry(&e, retries) => {
+                    tokio::time::sleep(self.retry_policy.backoff(retries)).await;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn send_once<C: Command>(&mut self, command: &C) -> Result<C::Output, Error> {
+        let mut line = String::new();
+        encode_command(command, &mut line)?;
+        self.receiver.start(&line, command.expects_prompt());
+        let written = match self.port.write_all(line.as_bytes()).await {
+            Ok(()) => self.port.flush().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = written {
+            self.receiver.cancel();
+            return Err(e.into());
+        }
+
+        let timeout = command.timeout().unwrap_or(self.timeout);
+        match tokio::time::timeout(timeout, self.receive()).await {
+            Ok(Ok(frame)) => self.receiver.decode(command, frame),
+            Ok(Err(e)) => {
+                self.receiver.cancel();
+                Err(e)
+            }
+            Err(_) => self.receiver.time_out(command),
+        }
+    }
+
+    async fn receive(&mut self) -> Result<Frame, Error> {
+        loop {
+            let mut buff = [0u8; 1024];
+            match self.port.read(&mut buff).await? {
+                n if n > 0 => {
+                    if let Some(frame) = self.receiver.receive(&buff[..n])? {
+                        return Ok(frame);
+                    }
+                }
+                _ => {
+                    return Err(Error::InvalidResponse(self.receiver.pending()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{GetWifiMode, Test, WifiMode};
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{duplex, DuplexStream};
+
+    /// Read what the interface sent to the module.
+    async fn read_command(module: &mut DuplexStream) -> Vec<u8> {
+        let mut buff = [0u8; 64];
+        let n = module.read(&mut buff).await.unwrap();
+        buff[..n].to_vec()
+    }
+
+    #[tokio::test]
+    async fn round_trip_over_duplex_stream() {
+        let (port, mut module) = duplex(256);
+        let mut interface = AsyncInterface::new(port);
+        let urcs = Arc::new(Mutex::new(Vec::new()));
+        let handler_urcs = urcs.clone();
+        interface.set_urc_handler(move |urc| handler_urcs.lock().unwrap().push(urc));
+
+        module
+            .write_all(b"AT+CWMODE?\r\nWIFI GOT IP\r\n+CWMODE:2\r\n\r\nOK\r\n")
+            .await
+            .unwrap();
+        assert_eq!(interface.send(GetWifiMode).await.unwrap(), WifiMode::ApMode);
+        assert_eq!(read_command(&mut module).await, b"AT+CWMODE?\r\n");
+        assert_eq!(*urcs.lock().unwrap(), [Urc::WifiGotIp]);
+    }
+
+    #[tokio::test]
+    async fn drops_late_response_to_command_that_timed_out() {
+        let (port, mut module) = duplex(256);
+        let mut interface = AsyncInterface::new(port);
+        interface.set_timeout(Duration::from_millis(50));
+
+        assert!(matches!(
+            interface.send(GetWifiMode).await,
+            Err(Error::Timeout)
+        ));
+        assert_eq!(read_command(&mut module).await, b"AT+CWMODE?\r\n");
+
+        module
+            .write_all(b"AT+CWMODE?\r\n+CWMODE:1\r\n\r\nOK\r\nAT\r\n\r\nOK\r\n")
+            .await
+            .unwrap();
+        assert!(interface.send(Test).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn reports_incomplete_response_when_stream_closes() {
+        let (port, mut module) = duplex(256);
+        let mut interface = AsyncInterface::new(port);
+
+        module.write_all(b"AT+CWMODE?\r\n+CWMO").await.unwrap();
+        module.shutdown().await.unwrap();
+        match interface.send(GetWifiMode).await {
+            Err(Error::InvalidResponse(pending)) => assert_eq!(pending, b"AT+CWMODE?\r\n+CWMO"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
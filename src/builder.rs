@@ -0,0 +1,159 @@
+use crate::{command, Error, Interface, RetryPolicy};
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+use std::time::Duration;
+
+/// Baud rates tried by [Interface::autodetect], most common first.
+pub const COMMON_BAUD_RATES: &[u32] = &[
+    115200, 9600, 74880, 57600, 38400, 19200, 230400, 460800, 921600,
+];
+
+/// Builder for an [Interface] over a serial port. Created with [Interface::builder].
+///
+/// The defaults are 115200 baud, 8N1, no flow control, a 30 second timeout and no retries.
+pub struct InterfaceBuilder<'a> {
+    port: &'a str,
+    baud_rate: u32,
+    data_bits: DataBits,
+    parity: Parity,
+    stop_bits: StopBits,
+    flow_control: FlowControl,
+    timeout: Duration,
+    retry_policy: RetryPolicy,
+    toggles: Vec<LineToggle>,
+}
+
+impl<'a> InterfaceBuilder<'a> {
+    pub(crate) fn new(port: &'a str) -> Self {
+        Self {
+            port,
+            baud_rate: 115200,
+            data_bits: DataBits::Eight,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            flow_control: FlowControl::None,
+            timeout: Duration::from_secs(30),
+            retry_policy: RetryPolicy::NONE,
+            toggles: Vec::new(),
+        }
+    }
+
+    pub fn baud_rate(mut self, baud_rate: u32) -> Self {
+        self.baud_rate = baud_rate;
+        self
+    }
+
+    pub fn data_bits(mut self, data_bits: DataBits) -> Self {
+        self.data_bits = data_bits;
+        self
+    }
+
+    pub fn parity(mut self, parity: Parity) -> Self {
+        self.parity = parity;
+        self
+    }
+
+    pub fn stop_bits(mut self, stop_bits: StopBits) -> Self {
+        self.stop_bits = stop_bits;
+        self
+    }
+
+    /// Set the flow control. Use `FlowControl::Hardware` for RTS/CTS.
+    pub fn flow_control(mut self, flow_control: FlowControl) -> Self {
+        self.flow_control = flow_control;
+        self
+    }
+
+    /// Set how long a command may take if it does not have its own [timeout](crate::Command::timeout).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set when failed commands are retried.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Drive `line` to `level` right after the port is opened, then wait for `hold` before the next step.
+    ///
+    /// Steps are executed in the order they are added. This can be used to reset a module or put it in a specific boot mode, e.g. on boards where DTR and RTS are wired to EN and GPIO0.
+    pub fn toggle(mut self, line: ControlLine, level: bool, hold: Duration) -> Self {
+        self.toggles.push(LineToggle { line, level, hold });
+        self
+    }
+
+    /// Open the serial port and run the toggle sequence.
+    pub fn open(self) -> Result<Interface, Error> {
+        let port = self.open_port()?;
+        Ok(self.configure(Interface::from_port(port)))
+    }
+
+    /// Open the serial port and run the toggle sequence, then find the baud rate the module responds on.
+    ///
+    /// Each rate in `rates` is tried in order by sending [command::Test], without retrying. The first rate where the module answers with `OK` is kept, and returned alongside the interface. The baud rate configured on this builder is ignored.
+    pub fn autodetect(self, rates: &[u32]) -> Result<(Interface, u32), Error> {
+        let mut interface = Interface::from_port(self.open_port()?);
+
+        for &rate in rates {
+            interface.port.set_baud_rate(rate)?;
+            interface.port.clear(ClearBuffer::All)?;
+
+            if interface.send(command::Test).is_ok() {
+                return Ok((self.configure(interface), rate));
+            }
+        }
+
+        Err(Error::BaudRateNotDetected)
+    }
+
+    fn configure(&self, mut interface: Interface) -> Interface {
+        interface.set_timeout(self.timeout);
+        interface.set_retry_policy(self.retry_policy);
+        interface
+    }
+
+    fn open_port(&self) -> Result<Box<dyn SerialPort>, Error> {
+        let mut port = serialport::new(self.port, self.baud_rate)
+            .data_bits(self.data_bits)
+            .parity(self.parity)
+            .stop_bits(self.stop_bits)
+            .flow_control(self.flow_control)
+            .timeout(self.timeout)
+            .open()?;
+
+        for toggle in &self.toggles {
+            toggle.apply(port.as_mut())?;
+        }
+
+        Ok(port)
+    }
+}
+
+/// A modem control line that can be driven when opening the port.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum ControlLine {
+    /// Data Terminal Ready
+    Dtr,
+    /// Request To Send
+    Rts,
+}
+
+/// A single step in the toggle sequence of an [InterfaceBuilder].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LineToggle {
+    pub line: ControlLine,
+    pub level: bool,
+    pub hold: Duration,
+}
+
+impl LineToggle {
+    fn apply(&self, port: &mut dyn SerialPort) -> Result<(), Error> {
+        match self.line {
+            ControlLine::Dtr => port.write_data_terminal_ready(self.level)?,
+            ControlLine::Rts => port.write_request_to_send(self.level)?,
+        }
+        std::thread::sleep(self.hold);
+        Ok(())
+    }
+}
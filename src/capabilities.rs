@@ -0,0 +1,325 @@
+use crate::command::{FirmwareFamily, FirmwareVersion, Persistence, SetSysStore};
+use crate::Error;
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use crate::command::{form::IsSupported, GetVersion};
+#[cfg(feature = "std")]
+use crate::{Interface, Transport};
+
+/// What the firmware of a module supports, so the right commands can be picked before sending them.
+///
+/// Use [Capabilities::probe] to find these for an [Interface](crate::Interface). For other interfaces, create them with [Capabilities::from_version].
+/// If the [family](Capabilities::family) of the firmware is [Unknown](FirmwareFamily::Unknown), send [IsSupported](crate::command::form::IsSupported) for each of the [PROBED_COMMANDS](Capabilities::PROBED_COMMANDS) and record the results with [set_supported](Capabilities::set_supported).
+#[derive(Clone, Debug)]
+pub struct Capabilities {
+    pub version: FirmwareVersion,
+    supported: Vec<&'static str>,
+}
+
+/// How a firmware chooses whether settings are saved to flash.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum PersistenceStyle {
+    /// Commands have a `_CUR` variant that is not saved and a `_DEF` variant that is, such as `AT+CWMODE_CUR`. Used by the NonOS firmware.
+    CurDef,
+    /// `AT+SYSSTORE` sets whether the following commands are saved. Used by ESP-AT 2.x.
+    SysStore,
+    /// Settings are always saved. Used by old firmware.
+    Always,
+}
+
+impl Capabilities {
+    /// The commands that [probe](Capabilities::probe) checks on firmware of an unknown family.
+    pub const PROBED_COMMANDS: &'static [&'static str] = &[
+        "+SYSSTORE",
+        "+CWMODE_CUR",
+        "+CWJAP_CUR",
+        "+CWLAPOPT",
+        "+CWSAP",
+        "+CWSAP_CUR",
+        "+CWDHCP",
+        "+CWDHCP_CUR",
+        "+CWDHCPS",
+        "+CWDHCPS_CUR",
+        "+CIPSTA",
+        "+CIPSTA_CUR",
+        "+CIPAP",
+        "+CIPAP_CUR",
+        "+CIPSTAMAC",
+        "+CIPSTAMAC_CUR",
+        "+CIPAPMAC",
+        "+CIPAPMAC_CUR",
+    ];
+
+    /// The capabilities of a firmware with the given version, without any supported commands.
+    pub fn new(version: FirmwareVersion) -> Self {
+        Self {
+            version,
+            supported: Vec::new(),
+        }
+    }
+
+    /// The capabilities of a firmware with the given version, with the [PROBED_COMMANDS](Capabilities::PROBED_COMMANDS) that its [family](FirmwareVersion::family) documents.
+    ///
+    /// Nothing is supported if the family is [Unknown](FirmwareFamily::Unknown).
+    pub fn from_version(version: FirmwareVersion) -> Self {
+        let supported = documented_commands(version.family()).to_vec();
+        Self { version, supported }
+    }
+
+    /// Get the firmware version of the module, and the commands it supports.
+    ///
+    /// For a known [FirmwareFamily] these are the ones the family documents, as with [from_version](Capabilities::from_version).
+    /// Otherwise [IsSupported](crate::command::form::IsSupported) is sent for each of the [PROBED_COMMANDS](Capabilities::PROBED_COMMANDS), and commands the module rejects or is too busy for are recorded as unsupported.
+    #[cfg(feature = "std")]
+    pub fn probe<T: Transport>(interface: &mut Interface<T>) -> Result<Self, Error> {
+        let version = interface.send(GetVersion)?;
+        if version.family() != FirmwareFamily::Unknown {
+            return Ok(Self::from_version(version));
+        }
+        let mut capabilities = Self::new(version);
+        for command in Self::PROBED_COMMANDS {
+            let supported = match interface.send(IsSupported(command)) {
+                Ok(supported) => supported,
+                Err(Error::Rejected(_)) | Err(Error::Busy(_)) => false,
+                Err(e) => return Err(e),
+            };
+            capabilities.set_supported(command, supported);
+        }
+        Ok(capabilities)
+    }
+
+    /// Record whether the command with the given name is supported.
+    pub fn set_supported(&mut self, command: &'static str, supported: bool) {
+        self.supported.retain(|c| *c != command);
+        if supported {
+            self.supported.push(command);
+        }
+    }
+
+    /// Whether the command with the given name, such as `+CWMODE_CUR`, is supported.
+    pub fn supports(&self, command: &str) -> bool {
+        self.supported.contains(&command)
+    }
+
+    /// Fail with [Error::Unsupported] if the command is not supported.
+    pub fn require(&self, command: &'static str) -> Result<(), Error> {
+        if self.supports(command) {
+            Ok(())
+        } else {
+            Err(Error::Unsupported(command))
+        }
+    }
+
+    pub fn family(&self) -> FirmwareFamily {
+        self.version.family()
+    }
+
+    /// How the firmware saves settings, based on whether `+SYSSTORE` or `+CWMODE_CUR` is supported.
+    pub fn persistence_style(&self) -> PersistenceStyle {
+        if self.supports("+SYSSTORE") {
+            PersistenceStyle::SysStore
+        } else if self.supports("+CWMODE_CUR") {
+            PersistenceStyle::CurDef
+        } else {
+            PersistenceStyle::Always
+        }
+    }
+
+    /// The [Persistence] to send setters with, so they are saved to flash if `save` is true, and the command that has to be sent before them.
+    ///
+    /// With [PersistenceStyle::SysStore], the returned [SetSysStore] has to be sent first, because the firmware saves settings by default.
+    /// With [PersistenceStyle::Always], settings are saved regardless.
+    pub fn persistence(&self, save: bool) -> (Persistence, Option<SetSysStore>) {
+        match self.persistence_style() {
+            PersistenceStyle::CurDef if save => (Persistence::Saved, None),
+            PersistenceStyle::CurDef => (Persistence::Current, None),
+            PersistenceStyle::SysStore => (Persistence::Default, Some(SetSysStore(save))),
+            PersistenceStyle::Always => (Persistence::Default, None),
+        }
+    }
+}
+
+/// The [PROBED_COMMANDS](Capabilities::PROBED_COMMANDS) that the documentation of each firmware family lists.
+fn documented_commands(family: FirmwareFamily) -> &'static [&'static str] {
+    match family {
+        // The commands without a suffix are deprecated, and always save the setting
+        FirmwareFamily::NonOs => &[
+            "+CWMODE_CUR",
+            "+CWJAP_CUR",
+            "+CWLAPOPT",
+            "+CWSAP",
+            "+CWSAP_CUR",
+            "+CWDHCP",
+            "+CWDHCP_CUR",
+            "+CWDHCPS_CUR",
+            "+CIPSTA",
+            "+CIPSTA_CUR",
+            "+CIPAP",
+            "+CIPAP_CUR",
+            "+CIPSTAMAC",
+            "+CIPSTAMAC_CUR",
+            "+CIPAPMAC",
+            "+CIPAPMAC_CUR",
+        ],
+        FirmwareFamily::EspAtV1 => &[
+            "+CWMODE_CUR",
+            "+CWJAP_CUR",
+            "+CWLAPOPT",
+            "+CWSAP_CUR",
+            "+CWDHCP_CUR",
+            "+CWDHCPS_CUR",
+            "+CIPSTA_CUR",
+            "+CIPAP_CUR",
+            "+CIPSTAMAC_CUR",
+            "+CIPAPMAC_CUR",
+        ],
+        FirmwareFamily::EspAtV2 => &[
+            "+SYSSTORE",
+            "+CWLAPOPT",
+            "+CWSAP",
+            "+CWDHCP",
+            "+CWDHCPS",
+            "+CIPSTA",
+            "+CIPAP",
+            "+CIPSTAMAC",
+            "+CIPAPMAC",
+        ],
+        FirmwareFamily::Unknown => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Command;
+    use alloc::string::String;
+
+    fn capabilities(supported: &[&'static str]) -> Capabilities {
+        let mut capabilities = Capabilities::new(FirmwareVersion::default());
+        for command in supported {
+            capabilities.set_supported(command, true);
+        }
+        capabilities
+    }
+
+    #[test]
+    fn picks_persistence_style_from_supported_commands() {
+        let esp_at = capabilities(&["+SYSSTORE", "+CWMODE_CUR"]);
+        assert_eq!(esp_at.persistence_style(), PersistenceStyle::SysStore);
+        assert!(matches!(
+            esp_at.persistence(true),
+            (Persistence::Default, Some(SetSysStore(true)))
+        ));
+        assert!(matches!(
+            esp_at.persistence(false),
+            (Persistence::Default, Some(SetSysStore(false)))
+        ));
+        let mut command = String::new();
+        let (_, sys_store) = esp_at.persistence(false);
+        sys_store.unwrap().encode(&mut command).unwrap();
+        assert_eq!(command, "AT+SYSSTORE=0\r\n");
+
+        let non_os = capabilities(&["+CWMODE_CUR"]);
+        assert_eq!(non_os.persistence_style(), PersistenceStyle::CurDef);
+        assert!(matches!(
+            non_os.persistence(true),
+            (Persistence::Saved, None)
+        ));
+        assert!(matches!(
+            non_os.persistence(false),
+            (Persistence::Current, None)
+        ));
+
+        let old = capabilities(&[]);
+        assert_eq!(old.persistence_style(), PersistenceStyle::Always);
+        assert!(matches!(
+            old.persistence(false),
+            (Persistence::Default, None)
+        ));
+    }
+
+    #[test]
+    fn records_supported_commands() {
+        let mut capabilities = capabilities(&["+CWLAPOPT"]);
+        assert!(capabilities.require("+CWLAPOPT").is_ok());
+        capabilities.set_supported("+CWLAPOPT", false);
+        assert!(!capabilities.supports("+CWLAPOPT"));
+        assert!(matches!(
+            capabilities.require("+CWLAPOPT"),
+            Err(Error::Unsupported("+CWLAPOPT"))
+        ));
+    }
+
+    #[test]
+    fn picks_commands_from_firmware_family() {
+        let version = |at_version: &str, sdk_version: &str| FirmwareVersion {
+            at_version: at_version.into(),
+            sdk_version: sdk_version.into(),
+            ..FirmwareVersion::default()
+        };
+
+        let esp_at = Capabilities::from_version(version("2.2.0.0", "v4.2.2-76-gefa6eca"));
+        assert_eq!(esp_at.persistence_style(), PersistenceStyle::SysStore);
+        assert!(esp_at.supports("+CWLAPOPT"));
+        assert!(!esp_at.supports("+CWMODE_CUR"));
+
+        let esp_at_v1 = Capabilities::from_version(version("1.1.0.0", "v3.0.5"));
+        assert_eq!(esp_at_v1.persistence_style(), PersistenceStyle::CurDef);
+        assert!(!esp_at_v1.supports("+CWSAP"));
+
+        let non_os = Capabilities::from_version(version("1.7.4.0", "3.0.4"));
+        assert_eq!(non_os.persistence_style(), PersistenceStyle::CurDef);
+        assert!(non_os.supports("+CWSAP"));
+
+        let unknown = Capabilities::from_version(version("", ""));
+        assert_eq!(unknown.persistence_style(), PersistenceStyle::Always);
+    }
+
+    #[cfg(feature = "std")]
+    mod probe {
+        use super::*;
+        use crate::interface::tests::MemoryTransport;
+
+        #[test]
+        fn takes_supported_commands_from_known_family() {
+            let transport = MemoryTransport::with_reads(&[Some(
+                b"AT+GMR\r\n\
+                  AT version:2.2.0.0(c6fa6bf - ESP32 - Jul  2 2021 06:44:05)\r\n\
+                  SDK version:v4.2.2-76-gefa6eca\r\n\
+                  \r\nOK\r\n",
+            )]);
+            let mut interface = Interface::with_transport(transport).unwrap();
+            let capabilities = Capabilities::probe(&mut interface).unwrap();
+            assert_eq!(capabilities.persistence_style(), PersistenceStyle::SysStore);
+            assert_eq!(interface.into_transport().output, b"AT+GMR\r\n");
+        }
+
+        #[test]
+        fn probes_unknown_family_with_query_form() {
+            let responses: Vec<Vec<u8>> =
+                core::iter::once(b"AT+GMR\r\nAT version:custom\r\n\r\nOK\r\n".to_vec())
+                    .chain(Capabilities::PROBED_COMMANDS.iter().map(|command| {
+                        let result: &str = match *command {
+                            "+SYSSTORE" => "+SYSSTORE:1\r\n\r\nOK",
+                            "+CWSAP" => "+CWSAP:\"ap\",\"secret\",1,3,4,0\r\n\r\nOK",
+                            "+CWDHCP" => "FAIL",
+                            "+CIPSTA" => "busy p...",
+                            _ => "ERR CODE:0x01090000\r\nERROR",
+                        };
+                        format!("AT{}?\r\n{}\r\n", command, result).into_bytes()
+                    }))
+                    .collect();
+            let reads: Vec<Option<&[u8]>> = responses.iter().map(|r| Some(&r[..])).collect();
+            let mut interface =
+                Interface::with_transport(MemoryTransport::with_reads(&reads)).unwrap();
+
+            let capabilities = Capabilities::probe(&mut interface).unwrap();
+            assert_eq!(capabilities.family(), FirmwareFamily::Unknown);
+            assert_eq!(capabilities.supported, ["+SYSSTORE", "+CWSAP"]);
+            assert_eq!(capabilities.persistence_style(), PersistenceStyle::SysStore);
+            let output = interface.into_transport().output;
+            assert!(output.starts_with(b"AT+GMR\r\nAT+SYSSTORE?\r\nAT+CWMODE_CUR?\r\n"));
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use super::parser::{FromValue, Value};
+use super::Argument;
+use crate::Error;
+use core::fmt::{self, Write};
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use core::str::FromStr;
+
+/// A MAC address, written as `aa:bb:cc:dd:ee:ff`.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct MacAddress(pub [u8; 6]);
+
+/// The MAC address of an access point.
+pub type Bssid = MacAddress;
+
+impl fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, byte) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_char(':')?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MacAddress {
+    type Err = Error;
+
+    /// Parse six hexadecimal bytes separated by `:`, such as `0c:d6:bd:0e:50:10`.
+    fn from_str(str: &str) -> Result<Self, Error> {
+        let mut bytes = [0; 6];
+        let mut parts = str.split(':');
+        for byte in &mut bytes {
+            *byte = parts
+                .next()
+                .filter(|part| part.len() == 2)
+                .and_then(|part| u8::from_str_radix(part, 16).ok())
+                .ok_or_else(|| Error::parse(str, "mac address"))?;
+        }
+        match parts.next() {
+            Some(_) => Err(Error::parse(str, "mac address")),
+            None => Ok(MacAddress(bytes)),
+        }
+    }
+}
+
+impl FromValue<'_> for MacAddress {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        parse_value(value)
+    }
+}
+
+impl Argument for MacAddress {
+    fn write(&self, output: &mut impl Write) -> fmt::Result {
+        write!(output, "\"{}\"", self)
+    }
+}
+
+macro_rules! ip_address {
+    ($($ty:ty),*) => {
+        $(
+            impl FromValue<'_> for $ty {
+                fn from_value(value: &Value<'_>) -> Option<Self> {
+                    parse_value(value)
+                }
+            }
+
+            impl Argument for $ty {
+                fn write(&self, output: &mut impl Write) -> fmt::Result {
+                    write!(output, "\"{}\"", self)
+                }
+            }
+        )*
+    };
+}
+
+ip_address!(IpAddr, Ipv4Addr, Ipv6Addr);
+
+/// Parse a quoted or unquoted string value with [FromStr].
+fn parse_value<T: FromStr>(value: &Value<'_>) -> Option<T> {
+    match value {
+        Value::Str(str) => str.parse().ok(),
+        Value::Raw(str) => str.parse().ok(),
+        _ => None,
+    }
+}
@@ -0,0 +1,131 @@
+use super::quote::AtString;
+use super::WifiMode;
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+/// A value that can be written as an argument of a set command, such as `AT+CWMODE=<mode>`.
+///
+/// Strings are written as an [AtString], `bool` as `0` or `1`, and `None` as an empty argument. Empty arguments at the end are left out.
+pub trait Argument {
+    fn write(&self, output: &mut impl Write) -> fmt::Result;
+
+    /// Whether the argument is written at all. Only `None` is not.
+    fn is_present(&self) -> bool {
+        true
+    }
+}
+
+macro_rules! display_argument {
+    ($($ty:ty),*) => {
+        $(
+            impl Argument for $ty {
+                fn write(&self, output: &mut impl Write) -> fmt::Result {
+                    write!(output, "{}", self)
+                }
+            }
+        )*
+    };
+}
+
+display_argument!(u8, u16, u32, u64, i8, i16, i32, i64, usize, AtString<'_>);
+
+impl Argument for bool {
+    fn write(&self, output: &mut impl Write) -> fmt::Result {
+        output.write_char(if *self { '1' } else { '0' })
+    }
+}
+
+impl Argument for str {
+    fn write(&self, output: &mut impl Write) -> fmt::Result {
+        write!(output, "{}", AtString(self))
+    }
+}
+
+impl Argument for String {
+    fn write(&self, output: &mut impl Write) -> fmt::Result {
+        self.as_str().write(output)
+    }
+}
+
+impl<T: Argument + ?Sized> Argument for &T {
+    fn write(&self, output: &mut impl Write) -> fmt::Result {
+        (**self).write(output)
+    }
+
+    fn is_present(&self) -> bool {
+        (**self).is_present()
+    }
+}
+
+impl<T: Argument> Argument for Option<T> {
+    fn write(&self, output: &mut impl Write) -> fmt::Result {
+        match self {
+            Some(value) => value.write(output),
+            None => Ok(()),
+        }
+    }
+
+    fn is_present(&self) -> bool {
+        self.as_ref().is_some_and(Argument::is_present)
+    }
+}
+
+impl Argument for WifiMode {
+    fn write(&self, output: &mut impl Write) -> fmt::Result {
+        (*self as u8).write(output)
+    }
+}
+
+/// The arguments of a set command, separated by commas. This is implemented for a single [Argument] and for tuples of them.
+pub trait Arguments {
+    fn write_arguments(&self, output: &mut impl Write) -> fmt::Result;
+}
+
+impl<T: Argument> Arguments for T {
+    fn write_arguments(&self, output: &mut impl Write) -> fmt::Result {
+        self.write(output)
+    }
+}
+
+macro_rules! tuple_arguments {
+    ($(($($name:ident),+)),*) => {
+        $(
+            impl<$($name: Argument),+> Arguments for ($($name,)+) {
+                #[allow(non_snake_case)]
+                fn write_arguments(&self, output: &mut impl Write) -> fmt::Result {
+                    let ($($name,)+) = self;
+                    // Empty arguments are only written if a later argument is present
+                    let mut skipped = 0;
+                    let mut first = true;
+                    $(
+                        if !core::mem::take(&mut first) {
+                            skipped += 1;
+                        }
+                        if $name.is_present() {
+                            for _ in 0..core::mem::take(&mut skipped) {
+                                output.write_char(',')?;
+                            }
+                            $name.write(output)?;
+                        }
+                    )+
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+tuple_arguments!(
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F),
+    (A, B, C, D, E, F, G),
+    (A, B, C, D, E, F, G, H),
+    (A, B, C, D, E, F, G, H, I),
+    (A, B, C, D, E, F, G, H, I, J),
+    (A, B, C, D, E, F, G, H, I, J, K),
+    (A, B, C, D, E, F, G, H, I, J, K, L)
+);
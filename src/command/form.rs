@@ -0,0 +1,191 @@
+//! The four forms of an AT command.
+//!
+//! A command such as `+CWMODE` is defined once, with a [Definition] and the forms it supports:
+//! - [Query] sends `AT+CWMODE?` and parses the current value
+//! - [Set] sends `AT+CWMODE=<arguments>`
+//! - [Test] sends `AT+CWMODE=?` and parses the allowed values
+//! - [Execute] sends `AT+CWMODE`
+//!
+//! ```no_run
+//! # fn main() -> Result<(), at_protocol::Error> {
+//! use at_protocol::command::form::{Query, Set, Test};
+//! use at_protocol::command::{Cwmode, WifiMode};
+//!
+//! let mut interface = at_protocol::Interface::new("/dev/ttyUSB0")?;
+//! let allowed = interface.send(Test(Cwmode))?;
+//! if allowed.contains(WifiMode::ApStationMode as i64) {
+//!     interface.send(Set(Cwmode, WifiMode::ApStationMode))?;
+//! }
+//! let mode = interface.send(Query(Cwmode))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::parser::{self, FromFields, FromValue, Value};
+use super::Arguments;
+use crate::{Command, Error, Outcome, Response};
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::ops::RangeInclusive;
+
+/// An AT command, such as `+CWMODE`.
+pub trait Definition {
+    /// The name of the command including the `+`, as it is sent after `AT`.
+    const NAME: &'static str;
+}
+
+/// A command that has the query form `AT+X?`.
+pub trait Queryable: Definition {
+    /// The current value, parsed from the `+X:` line of the response.
+    type Output: for<'a> FromFields<'a>;
+}
+
+/// A command that has the set form `AT+X=<arguments>`.
+pub trait Settable: Definition {
+    type Arguments: Arguments;
+}
+
+/// A command that has the test form `AT+X=?`.
+pub trait Testable: Definition {
+    /// The allowed values, parsed from the `+X:` line of the response. This is often [Allowed].
+    type Output: for<'a> FromFields<'a>;
+}
+
+/// A command that has the execute form `AT+X`.
+pub trait Executable: Definition {}
+
+/// The query form `AT+X?` of the command `D`.
+#[derive(Copy, Clone, Debug)]
+pub struct Query<D>(pub D);
+
+/// The set form `AT+X=<arguments>` of the command `D`.
+#[derive(Copy, Clone, Debug)]
+pub struct Set<D: Settable>(pub D, pub D::Arguments);
+
+/// The test form `AT+X=?` of the command `D`.
+#[derive(Copy, Clone, Debug)]
+pub struct Test<D>(pub D);
+
+/// The execute form `AT+X` of the command `D`.
+#[derive(Copy, Clone, Debug)]
+pub struct Execute<D>(pub D);
+
+impl<D: Queryable> Command for Query<D> {
+    type Output = D::Output;
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        write!(output, "AT{}?\r\n", D::NAME)?;
+        Ok(())
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Self::Output, Error> {
+        D::Output::from_fields(&parser::line(input, D::NAME)?)
+    }
+}
+
+impl<D: Settable> Command for Set<D> {
+    type Output = ();
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        write!(output, "AT{}=", D::NAME)?;
+        self.1.write_arguments(output)?;
+        output.write_str("\r\n")?;
+        Ok(())
+    }
+
+    fn decode(&self, _input: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<D: Testable> Command for Test<D> {
+    type Output = D::Output;
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        write!(output, "AT{}=?\r\n", D::NAME)?;
+        Ok(())
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Self::Output, Error> {
+        D::Output::from_fields(&parser::line(input, D::NAME)?)
+    }
+}
+
+impl<D: Executable> Command for Execute<D> {
+    type Output = ();
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        write!(output, "AT{}\r\n", D::NAME)?;
+        Ok(())
+    }
+
+    fn decode(&self, _input: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Check whether the module supports the command with the given name, such as `+CWMODE_CUR`, by sending its query form.
+///
+/// Outputs `false` if the module responds with `ERROR` or `FAIL`. Commands without a query form, such as `+CWLAPOPT`, are reported as unsupported.
+/// The test form is not used, because several firmwares do not document it.
+#[derive(Copy, Clone, Debug)]
+pub struct IsSupported(pub &'static str);
+
+impl Command for IsSupported {
+    type Output = bool;
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        write!(output, "AT{}?\r\n", self.0)?;
+        Ok(())
+    }
+
+    fn decode(&self, _input: &[u8]) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn decode_response(&self, response: &Response) -> Result<bool, Error> {
+        match response.outcome {
+            Outcome::Error(_) | Outcome::Fail => Ok(false),
+            _ => self.decode(response.result()?),
+        }
+    }
+}
+
+/// The values a test command allows for a parameter, such as `(0-3)` or `(0,1,5)`.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct Allowed {
+    ranges: Vec<RangeInclusive<i64>>,
+}
+
+impl Allowed {
+    /// Whether `value` is one of the allowed values.
+    pub fn contains(&self, value: i64) -> bool {
+        self.ranges.iter().any(|range| range.contains(&value))
+    }
+
+    /// The allowed values, as ranges. A single value is a range of one.
+    pub fn ranges(&self) -> &[RangeInclusive<i64>] {
+        &self.ranges
+    }
+}
+
+impl FromValue<'_> for Allowed {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        let values = match value {
+            Value::List(values) => values.as_slice(),
+            value => core::slice::from_ref(value),
+        };
+        let ranges = values
+            .iter()
+            .map(|value| match value {
+                Value::Int(int) => Some(*int..=*int),
+                Value::Raw(range) => {
+                    let (start, end) = range.split_once('-')?;
+                    Some(start.trim().parse().ok()?..=end.trim().parse().ok()?)
+                }
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Allowed { ranges })
+    }
+}
@@ -0,0 +1,365 @@
+use super::parser::{self, FromFields, Value};
+use super::{Argument, Arguments, FirmwareFamily, MacAddress, Persistence, WifiMode};
+use crate::{AtResponse, Command, Error};
+use core::fmt::Write;
+use core::net::{Ipv4Addr, Ipv6Addr};
+
+/// The name of a command for each [Persistence], such as `+CIPSTA`, `+CIPSTA_CUR` and `+CIPSTA_DEF`.
+type Names = [&'static str; 3];
+
+const CWDHCP: Names = ["+CWDHCP", "+CWDHCP_CUR", "+CWDHCP_DEF"];
+const CWDHCPS: Names = ["+CWDHCPS", "+CWDHCPS_CUR", "+CWDHCPS_DEF"];
+const CIPSTA: Names = ["+CIPSTA", "+CIPSTA_CUR", "+CIPSTA_DEF"];
+const CIPAP: Names = ["+CIPAP", "+CIPAP_CUR", "+CIPAP_DEF"];
+const CIPSTAMAC: Names = ["+CIPSTAMAC", "+CIPSTAMAC_CUR", "+CIPSTAMAC_DEF"];
+const CIPAPMAC: Names = ["+CIPAPMAC", "+CIPAPMAC_CUR", "+CIPAPMAC_DEF"];
+
+fn name(names: Names, persistence: Persistence) -> &'static str {
+    match persistence {
+        Persistence::Default => names[0],
+        Persistence::Current => names[1],
+        Persistence::Saved => names[2],
+    }
+}
+
+/// The names of the station or access point variant of a command, for `WifiMode::StationMode` or `WifiMode::ApMode`.
+fn interface_names(interface: WifiMode, station: Names, ap: Names) -> Result<Names, Error> {
+    match interface {
+        WifiMode::StationMode => Ok(station),
+        WifiMode::ApMode => Ok(ap),
+        WifiMode::ApStationMode => Err(Error::InvalidArgument(
+            "the interface needs to be StationMode or ApMode",
+        )),
+    }
+}
+
+/// Get on which interfaces DHCP is enabled.
+///
+/// NonOS firmware reports the interfaces in a different order than ESP-AT, so this needs to know the [FirmwareFamily], which [Capabilities::family](crate::Capabilities::family) gives.
+#[derive(Copy, Clone, Debug)]
+pub struct GetDhcp {
+    pub persistence: Persistence,
+    pub firmware: FirmwareFamily,
+}
+
+impl Command for GetDhcp {
+    type Output = DhcpState;
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        write!(output, "AT{}?\r\n", name(CWDHCP, self.persistence))?;
+        Ok(())
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<DhcpState, Error> {
+        let bits: u8 = parser::line(input, name(CWDHCP, self.persistence))?.get(0, "dhcp state")?;
+        // NonOS firmware uses bit 0 for the access point and bit 1 for the station, ESP-AT the other way around
+        let (station, ap) = match self.firmware {
+            FirmwareFamily::NonOs => (bits & 0b10 != 0, bits & 0b01 != 0),
+            _ => (bits & 0b01 != 0, bits & 0b10 != 0),
+        };
+        Ok(DhcpState { station, ap })
+    }
+}
+
+/// On which interfaces DHCP is enabled, as reported by [GetDhcp].
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub struct DhcpState {
+    /// Whether the station gets its address with DHCP.
+    pub station: bool,
+    /// Whether the access point runs a DHCP server.
+    pub ap: bool,
+}
+
+/// Enable or disable DHCP on the station, the access point, or both.
+///
+/// NonOS firmware takes the arguments in a different order than ESP-AT, as `<mode>,<en>`, so this needs to know the [FirmwareFamily]. Other families are sent the ESP-AT order.
+#[derive(Copy, Clone, Debug)]
+pub struct SetDhcp {
+    pub interfaces: WifiMode,
+    pub enable: bool,
+    pub persistence: Persistence,
+    pub firmware: FirmwareFamily,
+}
+
+impl Command for SetDhcp {
+    type Output = ();
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        write!(output, "AT{}=", name(CWDHCP, self.persistence))?;
+        match self.firmware {
+            FirmwareFamily::NonOs => {
+                let interfaces: u8 = match self.interfaces {
+                    WifiMode::ApMode => 0,
+                    WifiMode::StationMode => 1,
+                    WifiMode::ApStationMode => 2,
+                };
+                (interfaces, self.enable).write_arguments(output)?
+            }
+            _ => (self.enable, self.interfaces).write_arguments(output)?,
+        }
+        output.write_str("\r\n")?;
+        Ok(())
+    }
+
+    fn decode(&self, _input: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Get the addresses the DHCP server of the access point hands out.
+#[derive(Copy, Clone, Debug)]
+pub struct GetDhcpServer(pub Persistence);
+
+impl Command for GetDhcpServer {
+    type Output = DhcpRange;
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        write!(output, "AT{}?\r\n", name(CWDHCPS, self.0))?;
+        Ok(())
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<DhcpRange, Error> {
+        DhcpRange::from_fields(&parser::line(input, name(CWDHCPS, self.0))?)
+    }
+}
+
+/// The addresses the DHCP server of the access point hands out.
+#[derive(AtResponse, Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct DhcpRange {
+    /// How long an address is leased, in minutes, from 1 to 2880.
+    pub lease_time: u16,
+    pub start: Ipv4Addr,
+    pub end: Ipv4Addr,
+}
+
+/// Set the addresses the DHCP server of the access point hands out, or go back to the default addresses if this is `None`.
+///
+/// The range needs to be in the same subnet as the address of the access point, which can be set with [SetIpConfig].
+#[derive(Copy, Clone, Debug)]
+pub struct SetDhcpServer {
+    pub range: Option<DhcpRange>,
+    pub persistence: Persistence,
+}
+
+impl Command for SetDhcpServer {
+    type Output = ();
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        write!(output, "AT{}=", name(CWDHCPS, self.persistence))?;
+        match self.range {
+            Some(range) => {
+                (true, range.lease_time, range.start, range.end).write_arguments(output)?
+            }
+            None => false.write(output)?,
+        }
+        output.write_str("\r\n")?;
+        Ok(())
+    }
+
+    fn decode(&self, _input: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Get the address of the station with `AT+CIPSTA`, or of the access point with `AT+CIPAP`.
+#[derive(Copy, Clone, Debug)]
+pub struct GetIpConfig {
+    /// `WifiMode::StationMode` or `WifiMode::ApMode`.
+    pub interface: WifiMode,
+    pub persistence: Persistence,
+}
+
+impl Command for GetIpConfig {
+    type Output = IpConfig;
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        let names = interface_names(self.interface, CIPSTA, CIPAP)?;
+        write!(output, "AT{}?\r\n", name(names, self.persistence))?;
+        Ok(())
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<IpConfig, Error> {
+        // Response has a line for each address, such as:
+        // +CIPSTA:ip:"192.168.0.2"
+        // +CIPSTA:gateway:"192.168.0.1"
+        // +CIPSTA:netmask:"255.255.255.0"
+        let name = name(
+            interface_names(self.interface, CIPSTA, CIPAP)?,
+            self.persistence,
+        );
+        let (mut ip, mut gateway, mut netmask) = (None, None, None);
+        let (mut ip6_link_local, mut ip6_global) = (None, None);
+        for line in input.split(|b| *b == b'\n').map(<[u8]>::trim_ascii) {
+            let address = match line
+                .strip_prefix(name.as_bytes())
+                .and_then(|rest| rest.strip_prefix(b":"))
+            {
+                Some(address) => core::str::from_utf8(address)?,
+                None => continue,
+            };
+            let (key, fields) = parser::parse_line(address)?;
+            match key {
+                "ip" => ip = Some(fields.get(0, "ip")?),
+                "gateway" => gateway = Some(fields.get(0, "gateway")?),
+                "netmask" => netmask = Some(fields.get(0, "netmask")?),
+                "ip6ll" => ip6_link_local = Some(fields.get(0, "ip6ll")?),
+                "ip6gl" => ip6_global = Some(fields.get(0, "ip6gl")?),
+                _ => {}
+            }
+        }
+        Ok(IpConfig {
+            ip: ip.ok_or_else(|| Error::parse(input, "ip"))?,
+            gateway: gateway.ok_or_else(|| Error::parse(input, "gateway"))?,
+            netmask: netmask.ok_or_else(|| Error::parse(input, "netmask"))?,
+            ip6_link_local,
+            ip6_global,
+        })
+    }
+}
+
+/// The address of the station or the access point, as reported by [GetIpConfig].
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct IpConfig {
+    pub ip: Ipv4Addr,
+    pub gateway: Ipv4Addr,
+    pub netmask: Ipv4Addr,
+    /// The IPv6 link-local address, if IPv6 is enabled. Only reported by ESP-AT 2.x.
+    pub ip6_link_local: Option<Ipv6Addr>,
+    /// The IPv6 global address, if IPv6 is enabled. Only reported by ESP-AT 2.x.
+    pub ip6_global: Option<Ipv6Addr>,
+}
+
+/// Set a static address for the station with `AT+CIPSTA`, or for the access point with `AT+CIPAP`.
+///
+/// Setting the address of the station disables its DHCP client.
+#[derive(Copy, Clone, Debug)]
+pub struct SetIpConfig {
+    /// `WifiMode::StationMode` or `WifiMode::ApMode`.
+    pub interface: WifiMode,
+    pub ip: Ipv4Addr,
+    pub gateway: Ipv4Addr,
+    pub netmask: Ipv4Addr,
+    pub persistence: Persistence,
+}
+
+impl Command for SetIpConfig {
+    type Output = ();
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        let names = interface_names(self.interface, CIPSTA, CIPAP)?;
+        write!(output, "AT{}=", name(names, self.persistence))?;
+        (self.ip, self.gateway, self.netmask).write_arguments(output)?;
+        output.write_str("\r\n")?;
+        Ok(())
+    }
+
+    fn decode(&self, _input: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Get the MAC address of the station with `AT+CIPSTAMAC`, or of the access point with `AT+CIPAPMAC`.
+#[derive(Copy, Clone, Debug)]
+pub struct GetMacAddress {
+    /// `WifiMode::StationMode` or `WifiMode::ApMode`.
+    pub interface: WifiMode,
+    pub persistence: Persistence,
+}
+
+impl Command for GetMacAddress {
+    type Output = MacAddress;
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        let names = interface_names(self.interface, CIPSTAMAC, CIPAPMAC)?;
+        write!(output, "AT{}?\r\n", name(names, self.persistence))?;
+        Ok(())
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<MacAddress, Error> {
+        let names = interface_names(self.interface, CIPSTAMAC, CIPAPMAC)?;
+        parser::line(input, name(names, self.persistence))?.get(0, "mac")
+    }
+}
+
+/// Set the MAC address of the station with `AT+CIPSTAMAC`, or of the access point with `AT+CIPAPMAC`.
+///
+/// The station and the access point need different addresses, and bit 0 of the first byte needs to be 0.
+#[derive(Copy, Clone, Debug)]
+pub struct SetMacAddress {
+    /// `WifiMode::StationMode` or `WifiMode::ApMode`.
+    pub interface: WifiMode,
+    pub mac: MacAddress,
+    pub persistence: Persistence,
+}
+
+impl Command for SetMacAddress {
+    type Output = ();
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        let names = interface_names(self.interface, CIPSTAMAC, CIPAPMAC)?;
+        write!(output, "AT{}=", name(names, self.persistence))?;
+        self.mac.write(output)?;
+        output.write_str("\r\n")?;
+        Ok(())
+    }
+
+    fn decode(&self, _input: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Get the addresses of the station and the access point with `AT+CIFSR`.
+pub struct GetLocalAddresses;
+
+impl Command for GetLocalAddresses {
+    type Output = LocalAddresses;
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        output.write_str("AT+CIFSR\r\n").map_err(Into::into)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<LocalAddresses, Error> {
+        // Response has a line for each address, such as:
+        // +CIFSR:APIP,"192.168.4.1"
+        // +CIFSR:STAMAC,"0c:d6:bd:0e:50:10"
+        let mut addresses = LocalAddresses::default();
+        for fields in parser::lines(input, "+CIFSR") {
+            let fields = fields?;
+            let name = match fields.get(0, "name")? {
+                Value::Raw(name) => name,
+                _ => continue,
+            };
+            let (interface, key) = if let Some(key) = name.strip_prefix("STA") {
+                (&mut addresses.station, key)
+            } else if let Some(key) = name.strip_prefix("AP") {
+                (&mut addresses.ap, key)
+            } else {
+                continue;
+            };
+            match key {
+                "IP" => interface.ip = Some(fields.get(1, "ip")?),
+                "IP6LL" => interface.ip6_link_local = Some(fields.get(1, "ip6ll")?),
+                "IP6GL" => interface.ip6_global = Some(fields.get(1, "ip6gl")?),
+                "MAC" => interface.mac = Some(fields.get(1, "mac")?),
+                _ => {}
+            }
+        }
+        Ok(addresses)
+    }
+}
+
+/// The addresses reported by [GetLocalAddresses]. Only the interfaces that are enabled with [SetWifiMode](super::SetWifiMode) are reported.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub struct LocalAddresses {
+    pub station: InterfaceAddresses,
+    pub ap: InterfaceAddresses,
+}
+
+/// The addresses of the station or the access point. The IP address of a station that is not connected is `0.0.0.0`.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub struct InterfaceAddresses {
+    pub ip: Option<Ipv4Addr>,
+    pub ip6_link_local: Option<Ipv6Addr>,
+    pub ip6_global: Option<Ipv6Addr>,
+    pub mac: Option<MacAddress>,
+}
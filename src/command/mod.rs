@@ -1,11 +1,29 @@
+mod address;
+mod argument;
+pub mod form;
+mod ip;
+pub mod parser;
+mod persistence;
+mod quote;
+mod scan;
+mod soft_ap;
+mod version;
 mod wifi_mode;
 
+pub use self::address::{Bssid, MacAddress};
+pub use self::argument::{Argument, Arguments};
+pub use self::ip::*;
+pub use self::persistence::{Persistence, SetSysStore};
+pub use self::quote::AtString;
+pub use self::scan::*;
+pub use self::soft_ap::*;
+pub use self::version::{FirmwareFamily, FirmwareVersion, GetVersion, Version};
 pub use self::wifi_mode::*;
 
 macro_rules! simple_command {
     (
         $(#[$outer:meta])*
-        $name:ident => $blob:expr
+        $name:ident => $blob:expr $(, timeout: $timeout:expr)?
     ) => {
         $(#[$outer])*
         pub struct $name;
@@ -13,47 +31,36 @@ macro_rules! simple_command {
         impl crate::Command for $name {
             type Output = bool;
 
-            fn encode(&self, buffer: &mut impl std::io::Write) -> Result<(), crate::Error> {
-                buffer.write_all($blob).map_err(Into::into)
+            fn encode(&self, buffer: &mut impl core::fmt::Write) -> Result<(), crate::Error> {
+                buffer.write_str($blob).map_err(Into::into)
             }
 
             fn decode(&self, buffer: &[u8]) -> Result<bool, crate::Error> {
-                Ok(buffer == $blob)
+                Ok(buffer == $blob.as_bytes())
             }
+
+            $(
+                fn timeout(&self) -> Option<core::time::Duration> {
+                    Some($timeout)
+                }
+            )?
         }
     };
 }
 
 simple_command!(
     /// Test if AT system works correctly
-    Test => b"AT\r\n"
+    Test => "AT\r\n", timeout: core::time::Duration::from_millis(500)
 );
 
 simple_command!(
     /// Reset the module
     ///
     /// Note: Often your serial connection will be reset after running this command. To be safe, re-create your serial connection.
-    Restart => b"AT+RST\r\n"
+    Restart => "AT+RST\r\n"
 );
 
 simple_command!(
     /// Disconnect from the current AP
-    DisconnectFromAp => b"AT+CWQAP\r\n"
+    DisconnectFromAp => "AT+CWQAP\r\n"
 );
-
-pub struct GetVersion;
-
-impl crate::Command for GetVersion {
-    type Output = String;
-
-    fn encode(&self, buffer: &mut impl std::io::Write) -> Result<(), crate::Error> {
-        buffer.write_all(b"AT+GMR\r\n").map_err(Into::into)
-    }
-
-    fn decode(&self, buffer: &[u8]) -> Result<String, crate::Error> {
-        let str = std::str::from_utf8(buffer).unwrap();
-        let newline_pos = str.bytes().position(|b| b == b'\n').unwrap();
-        let str = (&str[newline_pos..]).trim();
-        Ok(str.to_owned())
-    }
-}
@@ -0,0 +1,335 @@
+//! Parse the information lines of a response, such as `+CWLAP:(3,"ssid",-50)`.
+//!
+//! A line is split into its name (`+CWLAP`) and its [Fields]. Each field is a [Value]: an integer, a quoted string, a list in parentheses, an empty field, or any other unquoted text.
+//! Typed values are taken out of the fields with [Fields::get], which fails with [Error::Parse] if a field is missing or has the wrong type.
+
+use super::quote::unescape;
+use crate::Error;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt::Write;
+
+/// A single field of a response line.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub enum Value<'a> {
+    /// Nothing between two commas.
+    Empty,
+    Int(i64),
+    /// A quoted string, with the quotes removed and escaped characters unescaped.
+    Str(Cow<'a, str>),
+    /// Unquoted text that is not an integer.
+    Raw(&'a str),
+    /// A list of values in parentheses.
+    List(Vec<Value<'a>>),
+}
+
+/// The fields of a response line, or of a list in that line.
+#[derive(Clone, Debug)]
+pub struct Fields<'a> {
+    line: &'a str,
+    values: Vec<Value<'a>>,
+}
+
+/// Convert a [Value] into a typed value.
+pub trait FromValue<'a>: Sized {
+    /// Returns `None` if the value does not have the right type.
+    fn from_value(value: &Value<'a>) -> Option<Self>;
+}
+
+/// Convert the [Fields] of a response line into a typed value. This can be derived with [AtResponse](crate::AtResponse).
+///
+/// A single [FromValue] is taken from the first field.
+pub trait FromFields<'a>: Sized {
+    /// The positions of the fields that hold secrets such as passwords, which are replaced in the logs by [redact_line].
+    const REDACTED: &'static [usize] = &[];
+
+    fn from_fields(fields: &Fields<'a>) -> Result<Self, Error>;
+}
+
+impl<'a, T: FromValue<'a>> FromFields<'a> for T {
+    fn from_fields(fields: &Fields<'a>) -> Result<Self, Error> {
+        fields.get(0, "value")
+    }
+}
+
+impl<'a> Fields<'a> {
+    /// The number of fields.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn values(&self) -> &[Value<'a>] {
+        &self.values
+    }
+
+    /// Get the field at `index` as a `T`. `field` names the field in the error if this fails.
+    pub fn get<T: FromValue<'a>>(&self, index: usize, field: &'static str) -> Result<T, Error> {
+        self.values
+            .get(index)
+            .and_then(T::from_value)
+            .ok_or_else(|| Error::parse(self.line, field))
+    }
+
+    /// Get the field at `index` as a `T`, or `None` if the field is missing or empty.
+    pub fn get_opt<T: FromValue<'a>>(
+        &self,
+        index: usize,
+        field: &'static str,
+    ) -> Result<Option<T>, Error> {
+        match self.values.get(index) {
+            None | Some(Value::Empty) => Ok(None),
+            Some(value) => T::from_value(value)
+                .map(Some)
+                .ok_or_else(|| Error::parse(self.line, field)),
+        }
+    }
+
+    /// Get the list in parentheses at `index`.
+    pub fn list(&self, index: usize, field: &'static str) -> Result<Fields<'a>, Error> {
+        match self.values.get(index) {
+            Some(Value::List(values)) => Ok(Fields {
+                line: self.line,
+                values: values.clone(),
+            }),
+            _ => Err(Error::parse(self.line, field)),
+        }
+    }
+}
+
+/// Split a line such as `+CMD:a,"b",(c,d)` into its name and fields.
+///
+/// A line without `:` has no fields. Surrounding whitespace is ignored.
+pub fn parse_line(line: &str) -> Result<(&str, Fields<'_>), Error> {
+    let line = line.trim();
+    let (name, rest) = match line.split_once(':') {
+        Some((name, rest)) => (name, rest),
+        None => {
+            return Ok((
+                line,
+                Fields {
+                    line,
+                    values: Vec::new(),
+                },
+            ))
+        }
+    };
+    let fields = parse_fields(rest).map_err(|_| Error::parse(line, "fields"))?;
+    Ok((name, Fields { line, ..fields }))
+}
+
+/// Parse fields separated by commas, such as `a,"b",(c,d)`, for lines that do not start with a name.
+pub fn parse_fields(fields: &str) -> Result<Fields<'_>, Error> {
+    let mut tokenizer = Tokenizer {
+        input: fields,
+        pos: 0,
+        depth: 0,
+    };
+    let values = tokenizer
+        .values(None)
+        .ok_or_else(|| Error::parse(fields, "fields"))?;
+    Ok(Fields {
+        line: fields,
+        values,
+    })
+}
+
+/// Parse the lines of `body` that start with `<name>:`, such as `+CWLAP:`.
+///
+/// Other lines are skipped. Lines that are not valid UTF-8 fail with [Error::Utf8].
+pub fn lines<'a>(
+    body: &'a [u8],
+    name: &'a str,
+) -> impl Iterator<Item = Result<Fields<'a>, Error>> + 'a {
+    body.split(|b| *b == b'\n')
+        .map(<[u8]>::trim_ascii)
+        .filter(move |line| {
+            line.strip_prefix(name.as_bytes())
+                .is_some_and(|rest| rest.starts_with(b":"))
+        })
+        .map(|line| parse_line(core::str::from_utf8(line)?).map(|(_, fields)| fields))
+}
+
+/// Parse the first line of `body` that starts with `<name>:`. Fails with [Error::Parse] if there is none.
+pub fn line<'a>(body: &'a [u8], name: &'static str) -> Result<Fields<'a>, Error> {
+    lines(body, name)
+        .next()
+        .unwrap_or_else(|| Err(Error::parse(body, name)))
+}
+
+/// Write `line` for logging, with the fields at the positions in `redacted` replaced by `"***"` if the line starts with `<name>:`. Other lines are written unchanged.
+///
+/// If the fields cannot be parsed, everything from the first field that fails is replaced.
+pub fn redact_line(
+    line: &str,
+    name: &str,
+    redacted: &[usize],
+    output: &mut impl Write,
+) -> Result<(), Error> {
+    let fields = match line
+        .strip_prefix(name)
+        .and_then(|rest| rest.strip_prefix(':'))
+    {
+        Some(fields) if !redacted.is_empty() => fields,
+        _ => return output.write_str(line).map_err(Into::into),
+    };
+    write!(output, "{}:", name)?;
+    let mut tokenizer = Tokenizer {
+        input: fields,
+        pos: 0,
+        depth: 0,
+    };
+    let mut index = 0;
+    loop {
+        let start = tokenizer.pos;
+        if tokenizer.value().is_none() {
+            output.write_str("***")?;
+            return Ok(());
+        }
+        if redacted.contains(&index) {
+            output.write_str("\"***\"")?;
+        } else {
+            output.write_str(&fields[start..tokenizer.pos])?;
+        }
+        match fields.as_bytes().get(tokenizer.pos) {
+            Some(b',') => {
+                tokenizer.pos += 1;
+                output.write_char(',')?;
+            }
+            None => return Ok(()),
+            Some(_) => {
+                output.write_str("***")?;
+                return Ok(());
+            }
+        }
+        index += 1;
+    }
+}
+
+/// How deeply lists may be nested. Responses nest at most a few levels, and deeper input would overflow the stack.
+const MAX_DEPTH: usize = 8;
+
+struct Tokenizer<'a> {
+    input: &'a str,
+    pos: usize,
+    /// The amount of lists the tokenizer is in.
+    depth: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    /// Parse values separated by commas up to `close`, or to the end of the input if `close` is `None`.
+    fn values(&mut self, close: Option<u8>) -> Option<Vec<Value<'a>>> {
+        let mut values = Vec::new();
+        loop {
+            values.push(self.value()?);
+            match self.input.as_bytes().get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b) if Some(*b) == close => {
+                    self.pos += 1;
+                    return Some(values);
+                }
+                None if close.is_none() => return Some(values),
+                _ => return None,
+            }
+        }
+    }
+
+    fn value(&mut self) -> Option<Value<'a>> {
+        let rest = &self.input[self.pos..];
+        match rest.as_bytes().first() {
+            Some(b'"') => {
+                let len = quoted_len(&rest[1..])?;
+                self.pos += len + 2;
+                Some(Value::Str(unescape(&rest[1..len + 1])))
+            }
+            Some(b'(') if self.depth < MAX_DEPTH => {
+                self.pos += 1;
+                self.depth += 1;
+                let values = self.values(Some(b')'));
+                self.depth -= 1;
+                values.map(Value::List)
+            }
+            Some(b'(') => None,
+            _ => {
+                let len = rest.find([',', ')']).unwrap_or(rest.len());
+                self.pos += len;
+                let token = rest[..len].trim();
+                Some(if token.is_empty() {
+                    Value::Empty
+                } else if let Ok(int) = token.parse() {
+                    Value::Int(int)
+                } else {
+                    Value::Raw(token)
+                })
+            }
+        }
+    }
+}
+
+/// The length of a quoted string up to its closing quote, skipping escaped characters.
+fn quoted_len(str: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (index, byte) in str.bytes().enumerate() {
+        match byte {
+            _ if escaped => escaped = false,
+            b'\\' => escaped = true,
+            b'"' => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+macro_rules! int_from_value {
+    ($($ty:ty),*) => {
+        $(
+            impl FromValue<'_> for $ty {
+                fn from_value(value: &Value<'_>) -> Option<Self> {
+                    match value {
+                        Value::Int(int) => (*int).try_into().ok(),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+int_from_value!(u8, u16, u32, u64, i8, i16, i32, i64, usize);
+
+impl FromValue<'_> for bool {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        match value {
+            Value::Int(0) => Some(false),
+            Value::Int(1) => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> FromValue<'a> for Cow<'a, str> {
+    fn from_value(value: &Value<'a>) -> Option<Self> {
+        match value {
+            Value::Str(str) => Some(str.clone()),
+            Value::Raw(str) => Some(Cow::Borrowed(str)),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue<'_> for String {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        Cow::from_value(value).map(Cow::into_owned)
+    }
+}
+
+impl<'a> FromValue<'a> for Value<'a> {
+    fn from_value(value: &Value<'a>) -> Option<Self> {
+        Some(value.clone())
+    }
+}
@@ -0,0 +1,33 @@
+use crate::AtCommand;
+
+/// Whether a setting is saved to flash, so it is kept after the module restarts.
+///
+/// Firmware that has `_CUR` and `_DEF` variants of commands, such as `AT+CWMODE_CUR`, uses [Current](Persistence::Current) and [Saved](Persistence::Saved).
+/// On ESP-AT 2.x this is set for all following commands with [SetSysStore] instead, and commands are sent with [Default](Persistence::Default).
+/// [Capabilities::persistence](crate::Capabilities::persistence) picks the right one for a module, and the [SetSysStore] to send first if it is needed.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub enum Persistence {
+    /// Send the command without a suffix, such as `AT+CWMODE`. Whether it is saved depends on the firmware.
+    #[default]
+    Default,
+    /// Send the `_CUR` variant, which is not saved.
+    Current,
+    /// Send the `_DEF` variant, which is saved.
+    Saved,
+}
+
+impl Persistence {
+    /// The suffix that is added to the name of the command.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Persistence::Default => "",
+            Persistence::Current => "_CUR",
+            Persistence::Saved => "_DEF",
+        }
+    }
+}
+
+/// Set whether the settings of the following commands are saved to flash. Only supported by ESP-AT 2.x.
+#[derive(AtCommand)]
+#[at(set = "+SYSSTORE")]
+pub struct SetSysStore(pub bool);
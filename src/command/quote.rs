@@ -0,0 +1,38 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+/// A string argument of an AT command.
+///
+/// This is formatted in double quotes, with `"`, `,` and `\` escaped by a backslash.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct AtString<'a>(pub &'a str);
+
+impl fmt::Display for AtString<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            if matches!(c, '"' | ',' | '\\') {
+                f.write_char('\\')?;
+            }
+            f.write_char(c)?;
+        }
+        f.write_char('"')
+    }
+}
+
+/// Remove the backslashes that escape characters in a quoted string in a response. This is the reverse of [AtString].
+pub(crate) fn unescape(str: &str) -> Cow<'_, str> {
+    if !str.contains('\\') {
+        return Cow::Borrowed(str);
+    }
+    let mut result = String::with_capacity(str.len());
+    let mut chars = str.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            c => result.push(c),
+        }
+    }
+    Cow::Owned(result)
+}
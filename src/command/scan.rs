@@ -0,0 +1,367 @@
+use super::parser::{self, Fields, FromValue, Value};
+use super::quote::AtString;
+use super::{Argument, Arguments, Bssid, MacAddress};
+use crate::{Command, Error};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::time::Duration;
+
+/// List all available access points in range.
+///
+/// This assumes the module prints the fields of [ScanFields::ALL], which is the default. Use [FilteredListAp] after changing this with [SetScanOptions].
+///
+/// Note: the chip needs to be in `WifiMode::StationMode`. You can check the current mode with [GetWifiMode](super::GetWifiMode) and you can change this with [SetWifiMode](super::SetWifiMode).
+pub struct ListAp;
+
+impl Command for ListAp {
+    type Output = Vec<AccessPoint>;
+
+    fn encode(&self, buffer: &mut impl Write) -> Result<(), Error> {
+        buffer.write_str("AT+CWLAP\r\n").map_err(Into::into)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        Some(Duration::from_secs(20))
+    }
+
+    fn decode(&self, buffer: &[u8]) -> Result<Vec<AccessPoint>, Error> {
+        decode_access_points(buffer, ScanFields::ALL)
+    }
+}
+
+/// List the access points in range that match a filter, with the scan settings of the module.
+///
+/// Filters that are `None` are left out.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FilteredListAp<'a> {
+    pub ssid: Option<&'a str>,
+    pub bssid: Option<Bssid>,
+    pub channel: Option<u8>,
+    pub scan_type: Option<ScanType>,
+    /// The minimum time to scan each channel, in milliseconds.
+    pub scan_time_min: Option<u16>,
+    /// The maximum time to scan each channel, in milliseconds.
+    pub scan_time_max: Option<u16>,
+    /// The fields the module prints, as set with [SetScanOptions].
+    pub fields: ScanFields,
+}
+
+impl Command for FilteredListAp<'_> {
+    type Output = Vec<AccessPoint>;
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        let arguments = (
+            self.ssid.map(AtString),
+            self.bssid,
+            self.channel,
+            self.scan_type,
+            self.scan_time_min,
+            self.scan_time_max,
+        );
+        output.write_str("AT+CWLAP")?;
+        if self.ssid.is_some()
+            || self.bssid.is_some()
+            || self.channel.is_some()
+            || self.scan_type.is_some()
+            || self.scan_time_min.is_some()
+            || self.scan_time_max.is_some()
+        {
+            output.write_char('=')?;
+            arguments.write_arguments(output)?;
+        }
+        output.write_str("\r\n")?;
+        Ok(())
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        Some(Duration::from_secs(20))
+    }
+
+    fn decode(&self, buffer: &[u8]) -> Result<Vec<AccessPoint>, Error> {
+        decode_access_points(buffer, self.fields)
+    }
+}
+
+fn decode_access_points(buffer: &[u8], scan_fields: ScanFields) -> Result<Vec<AccessPoint>, Error> {
+    // Response is in this format:
+    // "AT+CWLAP\r\n"
+    // "+CWLAP:(<ecn>,<ssid>,<rssi>,<mac>,<channel>[,<optional fields>])\r\n"
+    parser::lines(buffer, "+CWLAP")
+        .map(|line| AccessPoint::parse(&line?.list(0, "access point")?, scan_fields))
+        .collect()
+}
+
+/// Set which access points [ListAp] shows and which fields it prints, with `AT+CWLAPOPT`.
+#[derive(Copy, Clone, Debug)]
+pub struct SetScanOptions<'a> {
+    /// Sort the access points by signal strength, strongest first.
+    pub sort_by_rssi: bool,
+    /// The optional fields to print. The fields of [AccessPoint] that are not optional are always printed.
+    pub fields: ScanFields,
+    /// Only show access points with at least this signal strength, in dBm.
+    pub min_rssi: Option<i8>,
+    /// Only show access points with one of these encryption methods. Only supported by ESP-AT 2.x.
+    pub auth_modes: Option<&'a [ECN]>,
+}
+
+impl Command for SetScanOptions<'_> {
+    type Output = ();
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        let auth_mask = self.auth_modes.map(|modes| {
+            modes.iter().fold(0u32, |mask, ecn| {
+                mask | 1u32.checked_shl(u32::from(ecn.code())).unwrap_or(0)
+            })
+        });
+        output.write_str("AT+CWLAPOPT=")?;
+        (
+            self.sort_by_rssi,
+            self.fields.mask(),
+            self.min_rssi,
+            auth_mask,
+        )
+            .write_arguments(output)?;
+        output.write_str("\r\n")?;
+        Ok(())
+    }
+
+    fn decode(&self, _input: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// The optional fields of [AccessPoint] the module prints when scanning.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct ScanFields {
+    pub frequency_offset: bool,
+    pub frequency_calibration: bool,
+    pub pairwise_cipher: bool,
+    pub group_cipher: bool,
+    pub phy: bool,
+    pub wps: bool,
+}
+
+impl ScanFields {
+    /// All fields, which is what the module prints by default.
+    pub const ALL: ScanFields = ScanFields {
+        frequency_offset: true,
+        frequency_calibration: true,
+        pairwise_cipher: true,
+        group_cipher: true,
+        phy: true,
+        wps: true,
+    };
+
+    /// Only the fields that are not optional.
+    pub const NONE: ScanFields = ScanFields {
+        frequency_offset: false,
+        frequency_calibration: false,
+        pairwise_cipher: false,
+        group_cipher: false,
+        phy: false,
+        wps: false,
+    };
+
+    /// The optional fields in the order they are printed.
+    fn optional(self) -> [bool; 6] {
+        [
+            self.frequency_offset,
+            self.frequency_calibration,
+            self.pairwise_cipher,
+            self.group_cipher,
+            self.phy,
+            self.wps,
+        ]
+    }
+
+    /// The print mask of `AT+CWLAPOPT`. The first 5 bits are the fields that are always printed.
+    fn mask(self) -> u16 {
+        self.optional()
+            .iter()
+            .enumerate()
+            .filter(|(_, enabled)| **enabled)
+            .fold(0b11111, |mask, (bit, _)| mask | 1 << (bit + 5))
+    }
+}
+
+impl Default for ScanFields {
+    fn default() -> Self {
+        ScanFields::ALL
+    }
+}
+
+/// How the module scans a channel.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum ScanType {
+    /// Send probe requests.
+    Active = 0,
+    /// Only listen for beacons.
+    Passive = 1,
+}
+
+impl Argument for ScanType {
+    fn write(&self, output: &mut impl Write) -> fmt::Result {
+        (*self as u8).write(output)
+    }
+}
+
+#[derive(Debug)]
+pub struct AccessPoint {
+    pub ecn: ECN,
+    pub ssid: String,
+    pub rssi: i16,
+    pub mac: MacAddress,
+    pub channel: u8,
+    pub frequency_offset: Option<i32>,
+    pub frequency_calibration: Option<i32>,
+    pub pairwise_cipher: Option<Cipher>,
+    pub group_cipher: Option<Cipher>,
+    /// The 802.11 modes the access point supports.
+    pub phy: Option<Phy>,
+    /// Whether the access point supports WPS.
+    pub wps: Option<bool>,
+}
+
+impl AccessPoint {
+    /// Parse the fields in parentheses of a `+CWLAP:` line.
+    ///
+    /// The optional fields are only parsed if there are as many as `scan_fields` says, and are `None` otherwise.
+    /// With [ScanFields::ALL], older firmware prints only the first few, so those are parsed.
+    fn parse(fields: &Fields<'_>, scan_fields: ScanFields) -> Result<AccessPoint, Error> {
+        let optional = scan_fields.optional();
+        let count = optional.iter().filter(|enabled| **enabled).count();
+        let mut indices = [None; 6];
+        if fields.len() == 5 + count || scan_fields == ScanFields::ALL {
+            let mut index = 5;
+            for (enabled, slot) in optional.iter().zip(&mut indices) {
+                if *enabled && index < fields.len() {
+                    *slot = Some(index);
+                    index += 1;
+                }
+            }
+        }
+        let [frequency_offset, frequency_calibration, pairwise_cipher, group_cipher, phy, wps] =
+            indices;
+
+        Ok(AccessPoint {
+            ecn: fields.get(0, "ecn")?,
+            ssid: fields.get(1, "ssid")?,
+            rssi: fields.get(2, "rssi")?,
+            mac: fields.get(3, "mac")?,
+            channel: fields.get(4, "channel")?,
+            frequency_offset: optional_field(fields, frequency_offset, "frequency offset")?,
+            frequency_calibration: optional_field(
+                fields,
+                frequency_calibration,
+                "frequency calibration",
+            )?,
+            pairwise_cipher: optional_field(fields, pairwise_cipher, "pairwise cipher")?,
+            group_cipher: optional_field(fields, group_cipher, "group cipher")?,
+            phy: optional_field(fields, phy, "bgn")?,
+            wps: optional_field(fields, wps, "wps")?,
+        })
+    }
+}
+
+fn optional_field<'a, T: FromValue<'a>>(
+    fields: &Fields<'a>,
+    index: Option<usize>,
+    field: &'static str,
+) -> Result<Option<T>, Error> {
+    match index {
+        Some(index) => fields.get_opt(index, field),
+        None => Ok(None),
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum ECN {
+    Open,
+    WEP,
+    WPA_PSK,
+    WPA2_PSK,
+    WPA_WPA2_PSK,
+    Unknown(u8),
+}
+
+impl ECN {
+    /// The number the module uses for this encryption method.
+    fn code(self) -> u8 {
+        match self {
+            ECN::Open => 0,
+            ECN::WEP => 1,
+            ECN::WPA_PSK => 2,
+            ECN::WPA2_PSK => 3,
+            ECN::WPA_WPA2_PSK => 4,
+            ECN::Unknown(x) => x,
+        }
+    }
+}
+
+impl Argument for ECN {
+    fn write(&self, output: &mut impl Write) -> fmt::Result {
+        self.code().write(output)
+    }
+}
+
+impl FromValue<'_> for ECN {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        Some(match u8::from_value(value)? {
+            0 => ECN::Open,
+            1 => ECN::WEP,
+            2 => ECN::WPA_PSK,
+            3 => ECN::WPA2_PSK,
+            4 => ECN::WPA_WPA2_PSK,
+            x => ECN::Unknown(x),
+        })
+    }
+}
+
+/// The cipher an access point uses.
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+pub enum Cipher {
+    None,
+    Wep40,
+    Wep104,
+    Tkip,
+    Ccmp,
+    TkipCcmp,
+    AesCmac128,
+    Unknown(u8),
+}
+
+impl FromValue<'_> for Cipher {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        Some(match u8::from_value(value)? {
+            0 => Cipher::None,
+            1 => Cipher::Wep40,
+            2 => Cipher::Wep104,
+            3 => Cipher::Tkip,
+            4 => Cipher::Ccmp,
+            5 => Cipher::TkipCcmp,
+            6 => Cipher::AesCmac128,
+            x => Cipher::Unknown(x),
+        })
+    }
+}
+
+/// The 802.11 b/g/n modes an access point supports.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub struct Phy {
+    pub b: bool,
+    pub g: bool,
+    pub n: bool,
+}
+
+impl FromValue<'_> for Phy {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        let bits = u8::from_value(value)?;
+        Some(Phy {
+            b: bits & 0b001 != 0,
+            g: bits & 0b010 != 0,
+            n: bits & 0b100 != 0,
+        })
+    }
+}
@@ -0,0 +1,100 @@
+use super::parser::{self, FromFields};
+use super::{Argument, MacAddress, Persistence, ECN};
+use crate::{AtCommand, AtResponse, Command, Error};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::net::Ipv4Addr;
+
+/// Get the configuration of the access point the module runs in `WifiMode::ApMode`.
+#[derive(AtCommand)]
+#[at(query = "+CWSAP", response = "+CWSAP", output = SoftApConfig)]
+pub struct GetSoftAp;
+
+/// The configuration of the access point of the module, as reported by [GetSoftAp].
+#[derive(AtResponse, Clone, Debug, Eq, PartialEq)]
+pub struct SoftApConfig {
+    pub ssid: String,
+    #[at(redact)]
+    pub password: String,
+    pub channel: u8,
+    pub ecn: ECN,
+    /// How many stations can connect at the same time. Only reported by newer firmware.
+    pub max_connections: Option<u8>,
+    /// Whether the SSID is hidden. Only reported by newer firmware.
+    pub hidden: Option<bool>,
+}
+
+/// Configure the access point the module runs in `WifiMode::ApMode`.
+///
+/// The password is ignored for [ECN::Open], and needs to be 8 to 64 characters otherwise. WEP is not supported.
+#[derive(AtCommand, Copy, Clone, Debug)]
+#[at(set = "+CWSAP")]
+pub struct SetSoftAp<'a> {
+    pub ssid: &'a str,
+    #[at(redact)]
+    pub password: &'a str,
+    pub channel: u8,
+    pub ecn: ECN,
+    /// How many stations can connect at the same time, from 1 to 10.
+    pub max_connections: Option<u8>,
+    pub hidden: Option<bool>,
+    #[at(persistence)]
+    pub persistence: Persistence,
+}
+
+/// List the stations that are connected to the access point of the module.
+pub struct ListStations;
+
+impl Command for ListStations {
+    type Output = Vec<Station>;
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        output.write_str("AT+CWLIF\r\n").map_err(Into::into)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<Station>, Error> {
+        // Response is "+CWLIF:<ip>,<mac>" for each station, or "<ip>,<mac>" on older firmware
+        input
+            .split(|b| *b == b'\n')
+            .map(<[u8]>::trim_ascii)
+            .filter(|line| !line.is_empty() && !line.starts_with(b"AT+CWLIF"))
+            .map(|line| {
+                let line = core::str::from_utf8(line)?;
+                let fields = line.strip_prefix("+CWLIF:").unwrap_or(line);
+                Station::from_fields(&parser::parse_fields(fields)?)
+            })
+            .collect()
+    }
+}
+
+/// A station that is connected to the access point of the module.
+#[derive(AtResponse, Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct Station {
+    pub ip: Ipv4Addr,
+    pub mac: MacAddress,
+}
+
+/// Disconnect a station from the access point of the module, or all stations if this is `None`.
+///
+/// Disconnecting a single station is only supported by ESP-AT 2.x.
+#[derive(Copy, Clone, Debug)]
+pub struct KickStation(pub Option<MacAddress>);
+
+impl Command for KickStation {
+    type Output = ();
+
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        output.write_str("AT+CWQIF")?;
+        if let Some(mac) = self.0 {
+            output.write_char('=')?;
+            mac.write(output)?;
+        }
+        output.write_str("\r\n")?;
+        Ok(())
+    }
+
+    fn decode(&self, _input: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{Command, Error};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt::{self, Write};
+use core::hash::{Hash, Hasher};
+
+/// Get the version of the firmware on the module.
+pub struct GetVersion;
+
+impl Command for GetVersion {
+    type Output = FirmwareVersion;
+
+    fn encode(&self, buffer: &mut impl Write) -> Result<(), Error> {
+        buffer.write_str("AT+GMR\r\n").map_err(Into::into)
+    }
+
+    fn decode(&self, buffer: &[u8]) -> Result<FirmwareVersion, Error> {
+        Ok(FirmwareVersion::parse(core::str::from_utf8(buffer)?))
+    }
+}
+
+/// The firmware version reported by [GetVersion].
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub struct FirmwareVersion {
+    /// The version of the AT firmware, such as `2.2.0.0`.
+    pub at_version: Version,
+    /// The version of the SDK the firmware was built with, such as `3.0.4` or `v4.2.2-76-gefa6eca`.
+    pub sdk_version: Version,
+    /// When the firmware was built, if the module reports it.
+    pub compile_time: Option<String>,
+    /// The version of the firmware image, if the module reports it.
+    pub bin_version: Option<Version>,
+    /// The lines that are not recognized, such as the name of the manufacturer.
+    pub extra: Vec<String>,
+}
+
+impl FirmwareVersion {
+    /// Parse the lines of the `AT+GMR` response, such as:
+    ///
+    /// ```text
+    /// AT version:1.7.4.0(May 11 2020 19:13:04)
+    /// SDK version:3.0.4(9532ceb)
+    /// compile time:May 27 2020 10:12:17
+    /// Bin version(Wroom 02):1.7.4
+    /// ```
+    ///
+    /// Lines that are not recognized end up in [extra](FirmwareVersion::extra), and missing versions are left empty.
+    pub fn parse(text: &str) -> FirmwareVersion {
+        let mut version = FirmwareVersion::default();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with("AT+GMR") {
+                continue;
+            }
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) => (key_name(key), value.trim()),
+                None => ("", line),
+            };
+            match key {
+                "AT version" => version.at_version = Version::new(strip_detail(value)),
+                "SDK version" => version.sdk_version = Version::new(strip_detail(value)),
+                "compile time" => version.compile_time = Some(value.to_string()),
+                "Bin version" => version.bin_version = Some(Version::new(strip_detail(value))),
+                _ => version.extra.push(line.to_string()),
+            }
+        }
+        version
+    }
+
+    /// Which kind of firmware this is, guessed from the versions.
+    ///
+    /// AT firmware 1.x built on ESP-IDF reports an SDK version such as `v3.0.5`, while the NonOS SDK has no `v`.
+    pub fn family(&self) -> FirmwareFamily {
+        match self.at_version.major() {
+            Some(0) | Some(1) if self.sdk_version.as_str().starts_with('v') => {
+                FirmwareFamily::EspAtV1
+            }
+            Some(0) | Some(1) => FirmwareFamily::NonOs,
+            Some(_) => FirmwareFamily::EspAtV2,
+            None => FirmwareFamily::Unknown,
+        }
+    }
+}
+
+/// The kind of AT firmware running on the module. These support different sets of commands.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum FirmwareFamily {
+    /// The AT firmware of the ESP8266 NonOS SDK, version 1.x.
+    NonOs,
+    /// ESP-AT version 1.x, built on ESP-IDF.
+    EspAtV1,
+    /// ESP-AT version 2.x or later.
+    EspAtV2,
+    Unknown,
+}
+
+/// A version such as `2.2.0.0` or `v4.2.2-76-gefa6eca`.
+///
+/// Versions are compared by their leading numbers, so `1.7.4` is less than `2.0` and `2.2` equals `2.2.0.0`. Anything after the numbers, such as `-76-gefa6eca`, is ignored when comparing.
+#[derive(Clone, Debug, Default)]
+pub struct Version(String);
+
+impl Version {
+    pub fn new(version: impl Into<String>) -> Self {
+        Version(version.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The leading numbers of the version, such as `[4, 2, 2]` for `v4.2.2-76-gefa6eca`.
+    pub fn numbers(&self) -> Vec<u32> {
+        let version = self.0.strip_prefix('v').unwrap_or(&self.0);
+        let mut numbers = Vec::new();
+        for part in version.split('.') {
+            let digits = part
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(part.len());
+            match part[..digits].parse() {
+                Ok(number) => numbers.push(number),
+                Err(_) => break,
+            }
+            if digits < part.len() {
+                break;
+            }
+        }
+        numbers
+    }
+
+    /// The first number of the version, if there is one.
+    pub fn major(&self) -> Option<u32> {
+        self.numbers().first().copied()
+    }
+
+    /// The numbers without trailing zeros, so `2.2` and `2.2.0.0` compare equal.
+    fn significant(&self) -> Vec<u32> {
+        let mut numbers = self.numbers();
+        while numbers.last() == Some(&0) {
+            numbers.pop();
+        }
+        numbers
+    }
+}
+
+impl From<&str> for Version {
+    fn from(version: &str) -> Self {
+        Version::new(version)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.significant() == other.significant()
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.significant().cmp(&other.significant())
+    }
+}
+
+impl Hash for Version {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.significant().hash(state)
+    }
+}
+
+/// The name of a line such as `compile time(3a696ba):...` without the part in parentheses.
+fn key_name(key: &str) -> &str {
+    key.split('(').next().unwrap_or(key).trim()
+}
+
+/// A version such as `1.7.4.0(May 11 2020 19:13:04)` without the part in parentheses.
+fn strip_detail(value: &str) -> &str {
+    value.split('(').next().unwrap_or(value).trim()
+}
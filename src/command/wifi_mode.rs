@@ -1,4 +1,12 @@
-use crate::{Command, Error};
+use super::form::{Allowed, Definition, Queryable, Settable, Testable};
+use super::parser::{self, FromFields, FromValue, Value};
+use super::quote::AtString;
+use super::{Argument, Arguments, Bssid, Persistence};
+use crate::{AtCommand, AtResponse, Command, Error, JoinFailure, Outcome, Response};
+use alloc::format;
+use alloc::string::String;
+use core::fmt::{self, Write};
+use core::time::Duration;
 
 /// Get the current wifi mode of the module.
 pub struct GetWifiMode;
@@ -6,49 +14,43 @@ pub struct GetWifiMode;
 impl Command for GetWifiMode {
     type Output = WifiMode;
 
-    fn encode(&self, buffer: &mut impl std::io::Write) -> Result<(), Error> {
-        buffer.write_all(b"AT+CWMODE?\r\n").map_err(Into::into)
+    fn encode(&self, buffer: &mut impl Write) -> Result<(), Error> {
+        buffer.write_str("AT+CWMODE?\r\n").map_err(Into::into)
     }
 
     fn decode(&self, buffer: &[u8]) -> Result<WifiMode, Error> {
         // Response is:
         // "AT+CWMODE?
         //  +CWMODE:1\r\n"
-        // so we look for the first ':', then take the next char
-
-        let invalid_response = || Error::Custom(format!("Invalid response: {:?}", buffer));
-
-        let index = buffer
-            .iter()
-            .position(|b| *b == b':')
-            .ok_or_else(invalid_response)?;
-        let next = buffer.get(index + 1).ok_or_else(invalid_response)?;
-
-        match next {
-            b'1' => Ok(WifiMode::StationMode),
-            b'2' => Ok(WifiMode::ApMode),
-            b'3' => Ok(WifiMode::ApStationMode),
-            x => Err(Error::Custom(format!(
-                "Unknown wifi mode: {}, expected {}, {} or {}",
-                x, b'1', b'2', b'3'
-            ))),
-        }
+        parser::line(buffer, "+CWMODE")?.get(0, "wifi mode")
     }
 }
-/// Set the current wifi mode of the module.
-pub struct SetWifiMode(pub WifiMode);
 
-impl Command for SetWifiMode {
-    type Output = ();
+/// Set the current wifi mode of the module, and whether it is saved.
+#[derive(AtCommand)]
+#[at(set = "+CWMODE")]
+pub struct SetWifiMode(pub WifiMode, #[at(persistence)] pub Persistence);
 
-    fn encode(&self, buffer: &mut impl std::io::Write) -> Result<(), Error> {
-        write!(buffer, "AT+CWMODE={}\r\n", self.0 as u8)?;
-        Ok(())
-    }
+/// The `AT+CWMODE` command, which gets, sets and lists the [WifiMode]s with the [forms](super::form) of the command.
+///
+/// `Query(Cwmode)` is the same as [GetWifiMode], and `Set(Cwmode, mode)` the same as [SetWifiMode] with [Persistence::Default].
+#[derive(Copy, Clone, Debug)]
+pub struct Cwmode;
 
-    fn decode(&self, _buffer: &[u8]) -> Result<(), Error> {
-        Ok(())
-    }
+impl Definition for Cwmode {
+    const NAME: &'static str = "+CWMODE";
+}
+
+impl Queryable for Cwmode {
+    type Output = WifiMode;
+}
+
+impl Settable for Cwmode {
+    type Arguments = WifiMode;
+}
+
+impl Testable for Cwmode {
+    type Output = Allowed;
 }
 
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
@@ -58,168 +60,217 @@ pub enum WifiMode {
     ApStationMode = 3,
 }
 
-/// List all available access points in range.
-///
-/// Note: the chip needs to be in `WifiMode::StationMode`. You can check the current mode with [GetWifiMode] and you can change this with [SetWifiMode].
-pub struct ListAp;
-
-impl Command for ListAp {
-    type Output = Vec<AccessPoint>;
-
-    fn encode(&self, buffer: &mut impl std::io::Write) -> Result<(), Error> {
-        buffer.write_all(b"AT+CWLAP\r\n").map_err(Into::into)
-    }
-
-    fn decode(&self, buffer: &[u8]) -> Result<Vec<AccessPoint>, Error> {
-        // Response is in this format:
-        // "AT+CWLAP\r\n"
-        // "+CWLAP:(<ecn>,<ssid>,<rssi>,<mac>)\r\n"
-        // we make the assumption this is valid UTF8 just to make the parsing easier
-        let str = std::str::from_utf8(buffer).unwrap();
-        let mut result = Vec::new();
-        for line in str.lines().filter(|l| l.starts_with("+CWLAP:(")) {
-            let open_bracket = line.bytes().position(|b| b == b'(').unwrap();
-            let line = &line[open_bracket + 1..];
-
-            let (ecn, line) = try_get_string_until(line, b',')?;
-            let (ssid, line) = try_get_string_until(line, b',')?;
-            let (rssi, line) = try_get_string_until(line, b',')?;
-            let (mac, line) = try_get_string_until(line, b',')?;
-            let (channel, _line) = try_get_string_until(line, b')')?;
-
-            let ecn: u8 = match ecn.parse() {
-                Ok(ecn) => ecn,
-                Err(e) => {
-                    return Err(Error::Custom(format!(
-                        "Invalid ECN value {:?}: {:?}",
-                        ecn, e
-                    )))
-                }
-            };
-
-            let ecn = match ecn {
-                0 => ECN::Open,
-                1 => ECN::WEP,
-                2 => ECN::WPA_PSK,
-                3 => ECN::WPA2_PSK,
-                4 => ECN::WPA_WPA2_PSK,
-                x => ECN::Unknown(x),
-            };
-            let rssi: i16 = match rssi.parse() {
-                Ok(rssi) => rssi,
-                Err(e) => {
-                    return Err(Error::Custom(format!(
-                        "Invalid RSSI value: {:?}: {:?}",
-                        rssi, e
-                    )))
-                }
-            };
-            let channel: u8 = match channel.parse() {
-                Ok(channel) => channel,
-                Err(e) => {
-                    return Err(Error::Custom(format!(
-                        "Invalid channel value: {:?}: {:?}",
-                        channel, e
-                    )))
-                }
-            };
-
-            result.push(AccessPoint {
-                ecn,
-                ssid: ssid.to_owned(),
-                rssi,
-                mac: mac.to_owned(),
-                channel,
-            })
+impl FromValue<'_> for WifiMode {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        match value {
+            Value::Int(1) => Some(WifiMode::StationMode),
+            Value::Int(2) => Some(WifiMode::ApMode),
+            Value::Int(3) => Some(WifiMode::ApStationMode),
+            _ => None,
         }
-
-        Ok(result)
     }
 }
 
-fn try_get_string_until(str: &str, find: u8) -> Result<(&str, &str), Error> {
-    let mut in_quotes = false;
-    for (index, byte) in str.bytes().enumerate() {
-        if byte == b'"' {
-            in_quotes = !in_quotes;
-        }
-        if byte == find && !in_quotes {
-            let mut lhs = &str[..index];
-            if lhs.len() >= 2 && lhs.starts_with('"') && lhs.ends_with('"') {
-                lhs = &lhs[1..lhs.len() - 1];
-            }
-            let rhs = &str[index + 1..];
-            return Ok((lhs, rhs));
-        }
-    }
-    Err(Error::Custom(format!(
-        "Could not find character {:?} in string {:?}",
-        find, str
-    )))
-}
-
-#[derive(Debug)]
-pub struct AccessPoint {
-    pub ecn: ECN,
-    pub ssid: String,
-    pub rssi: i16,
-    pub mac: String, // Should this be a byte array?
-    pub channel: u8,
-}
-
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
-#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
-pub enum ECN {
-    Open,
-    WEP,
-    WPA_PSK,
-    WPA2_PSK,
-    WPA_WPA2_PSK,
-    Unknown(u8),
-}
-
+/// Connect to an access point.
+///
+/// Only the SSID and password are required. The other options are left out when they are `None`, and are only supported by newer firmware:
+///
+/// ```
+/// # use at_protocol::command::{ConnectToAp, ScanMode};
+/// let command = ConnectToAp {
+///     scan_mode: Some(ScanMode::AllChannels),
+///     ..ConnectToAp::new("ssid", "password")
+/// };
+/// ```
+///
+/// If connecting fails, the reason is returned as [Error::ConnectFailed].
+#[derive(Copy, Clone, Debug)]
 pub struct ConnectToAp<'a> {
     pub ssid: &'a str,
     pub password: &'a str,
+    /// Only connect to the access point with this MAC address, when there are several with the same SSID.
+    pub bssid: Option<Bssid>,
+    /// Refuse to connect to access points with open or WEP authentication.
+    pub pci_en: Option<bool>,
+    /// The seconds between attempts to reconnect, from 1 to 7200. `0` disables reconnecting.
+    pub reconnect_interval: Option<u16>,
+    /// The interval for listening to beacons, in AP beacon intervals from 1 to 100.
+    pub listen_interval: Option<u16>,
+    pub scan_mode: Option<ScanMode>,
+    /// How many seconds the module tries to connect before it fails, from 3 to 600.
+    pub jap_timeout: Option<u16>,
+    pub pmf: Option<Pmf>,
+    /// Whether the access point is saved, so the module connects to it again after a restart.
+    pub persistence: Persistence,
+}
+
+impl<'a> ConnectToAp<'a> {
+    /// Connect to the given SSID, without any of the other options.
+    pub fn new(ssid: &'a str, password: &'a str) -> Self {
+        Self {
+            ssid,
+            password,
+            bssid: None,
+            pci_en: None,
+            reconnect_interval: None,
+            listen_interval: None,
+            scan_mode: None,
+            jap_timeout: None,
+            pmf: None,
+            persistence: Persistence::Default,
+        }
+    }
+
+    fn encode_with_password(&self, password: &str, output: &mut impl Write) -> Result<(), Error> {
+        write!(output, "AT+CWJAP{}=", self.persistence.suffix())?;
+        (
+            AtString(self.ssid),
+            AtString(password),
+            self.bssid,
+            self.pci_en,
+            self.reconnect_interval,
+            self.listen_interval,
+            self.scan_mode,
+            self.jap_timeout,
+            self.pmf,
+        )
+            .write_arguments(output)?;
+        output.write_str("\r\n")?;
+        Ok(())
+    }
 }
 
 impl<'a> Command for ConnectToAp<'a> {
     type Output = ();
 
-    fn encode(&self, output: &mut impl std::io::Write) -> Result<(), Error> {
-        write!(output, "AT+CWJAP={:?},{:?}\r\n", self.ssid, self.password)?;
-        Ok(())
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        self.encode_with_password(self.password, output)
+    }
+
+    fn encode_redacted(&self, output: &mut impl Write) -> Result<(), Error> {
+        self.encode_with_password("***", output)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        // Give the module time to report the failure after its own timeout
+        let jap_timeout = self.jap_timeout.map_or(0, |seconds| u64::from(seconds) + 5);
+        Some(Duration::from_secs(jap_timeout.max(20)))
     }
 
     fn decode(&self, _input: &[u8]) -> Result<Self::Output, Error> {
         Ok(())
     }
+
+    fn decode_response(&self, response: &Response) -> Result<Self::Output, Error> {
+        // On failure the module sends "+CWJAP:<error code>" before "FAIL", or before "ERROR" on ESP-AT 2.x
+        // The name has the same suffix as the command, such as "+CWJAP_CUR:<error code>"
+        if matches!(response.outcome, Outcome::Fail | Outcome::Error(_)) {
+            let name = format!("+CWJAP{}", self.persistence.suffix());
+            let code = parser::lines(response.body, &name)
+                .next()
+                .and_then(|fields| fields.and_then(|f| f.get(0, "error code")).ok());
+            if let Some(code) = code {
+                return Err(Error::ConnectFailed(JoinFailure::from_code(code)));
+            }
+        }
+        self.decode(response.result()?)
+    }
 }
 
+/// Get the access point the module is connected to, or `None` if it is not connected.
 pub struct GetConnectedAp;
 
 impl Command for GetConnectedAp {
-    type Output = Option<String>;
+    type Output = Option<ConnectedAp>;
 
-    fn encode(&self, output: &mut impl std::io::Write) -> Result<(), Error> {
-        output.write_all(b"AT+CWJAP?\r\n").map_err(Into::into)
+    fn encode(&self, output: &mut impl Write) -> Result<(), Error> {
+        output.write_str("AT+CWJAP?\r\n").map_err(Into::into)
     }
 
     fn decode(&self, input: &[u8]) -> Result<Self::Output, Error> {
         // response: "AT+CWJAP?\r\n+CWJAP:\"<SSID>\",\"0c:d6:bd:0e:50:10\",8,-49,0,0,0,0"
         // or: "AT+CWJAP?\r\nNo AP"
-        let input = std::str::from_utf8(input).unwrap();
-        let line = input.lines().nth(1).unwrap().trim();
-        if line == "No AP" {
+        if input
+            .split(|b| *b == b'\n')
+            .any(|line| line.trim_ascii() == b"No AP")
+        {
             return Ok(None);
         }
-        let colon = line.bytes().position(|b| b == b':').unwrap();
-        let comma = line.bytes().position(|b| b == b',').unwrap();
-        let mut name = &line[colon + 1..comma];
-        if name.len() >= 2 && name.starts_with('"') && name.ends_with('"') {
-            name = &name[1..name.len() - 1];
+        ConnectedAp::from_fields(&parser::line(input, "+CWJAP")?).map(Some)
+    }
+}
+
+/// The access point the module is connected to, as reported by [GetConnectedAp].
+///
+/// The fields after `rssi` are only reported by newer firmware.
+#[derive(AtResponse, Clone, Debug, Eq, PartialEq)]
+pub struct ConnectedAp {
+    pub ssid: String,
+    pub bssid: Bssid,
+    pub channel: u8,
+    pub rssi: i16,
+    /// Whether the module refuses to connect to access points with open or WEP authentication.
+    pub pci_en: Option<bool>,
+    /// The seconds between attempts to reconnect.
+    pub reconnect_interval: Option<u16>,
+    /// The interval for listening to beacons, in AP beacon intervals.
+    pub listen_interval: Option<u16>,
+    pub scan_mode: Option<ScanMode>,
+    pub pmf: Option<Pmf>,
+}
+
+/// How the module scans for the access point to connect to.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum ScanMode {
+    /// Connect to the first matching access point that is found.
+    Fast = 0,
+    /// Scan all channels and connect to the matching access point with the strongest signal.
+    AllChannels = 1,
+}
+
+impl Argument for ScanMode {
+    fn write(&self, output: &mut impl Write) -> fmt::Result {
+        (*self as u8).write(output)
+    }
+}
+
+impl FromValue<'_> for ScanMode {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        match value {
+            Value::Int(0) => Some(ScanMode::Fast),
+            Value::Int(1) => Some(ScanMode::AllChannels),
+            _ => None,
         }
+    }
+}
+
+/// Protected Management Frames (802.11w) settings.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub struct Pmf {
+    /// Whether PMF is supported.
+    pub capable: bool,
+    /// Whether PMF is required, so access points without it are not used.
+    pub required: bool,
+}
+
+impl Pmf {
+    fn bits(self) -> u8 {
+        u8::from(self.capable) | u8::from(self.required) << 1
+    }
+}
+
+impl Argument for Pmf {
+    fn write(&self, output: &mut impl Write) -> fmt::Result {
+        self.bits().write(output)
+    }
+}
 
-        Ok(Some(name.to_owned()))
+impl FromValue<'_> for Pmf {
+    fn from_value(value: &Value<'_>) -> Option<Self> {
+        let bits = u8::from_value(value)?;
+        Some(Pmf {
+            capable: bits & 0b01 != 0,
+            required: bits & 0b10 != 0,
+        })
     }
 }
@@ -0,0 +1,170 @@
+use crate::framer::Frame;
+use crate::receiver::Receiver;
+use crate::{encode_command, Command, Error, Urc};
+use alloc::boxed::Box;
+use embedded_io::{Read, Write};
+
+/// An [Interface](crate::Interface) for microcontroller hosts, over any [embedded_io] serial port.
+///
+/// Commands and responses are stored in fixed buffers of `N` bytes. A response that does not fit fails the command with [Error::BufferFull] once it is received completely, so the next command is not confused by the rest of it. The default fits a scan of a few dozen access points, [SetScanOptions](crate::command::SetScanOptions) can limit the size of a scan for smaller buffers.
+///
+/// Reads block until the port returns data, so any timeout has to be implemented by the port.
+///
+/// Decoded outputs, errors and URCs are still allocated, so this needs a global allocator.
+pub struct EmbeddedInterface<T, const N: usize = 4096> {
+    port: T,
+    receiver: Receiver<heapless::Vec<u8, N>>,
+}
+
+impl<T: Read + Write, const N: usize> EmbeddedInterface<T, N> {
+    pub fn new(port: T) -> Self {
+        Self {
+            port,
+            receiver: Receiver::new(heapless::Vec::new()),
+        }
+    }
+
+    /// Set the callback that receives the [Urc]s sent by the module.
+    ///
+    /// URCs are only read while a command is being sent. They are not part of the response that is passed to the command.
+    pub fn set_urc_handler(&mut self, handler: impl FnMut(Urc) + Send + 'static) {
+        self.receiver.set_urc_handler(Box::new(handler));
+    }
+
+    /// Consume the interface, returning the underlying port.
+    pub fn into_inner(self) -> T {
+        self.port
+    }
+
+    pub fn send<C: Command>(&mut self, command: C) -> Result<C::Output, Error> {
+        let mut line = heapless::String::<N>::new();
+        encode_command(&command, &mut line)?;
+        self.receiver.start(&line, command.expects_prompt());
+        let written = self
+            .port
+            .write_all(line.as_bytes())
+            .and_then(|()| self.port.flush());
+        if let Err(e) = written {
+            self.receiver.cancel();
+            return Err(io_error(e));
+        }
+
+        match self.receive() {
+            Ok(frame) => self.receiver.decode(&command, frame),
+            Err(Error::Timeout) => self.receiver.time_out(&command),
+            Err(e) => {
+                self.receiver.cancel();
+                Err(e)
+            }
+        }
+    }
+
+    fn receive(&mut self) -> Result<Frame, Error> {
+        loop {
+            let mut buff = [0u8; 64];
+            match self.port.read(&mut buff).map_err(io_error)? {
+                n if n > 0 => {
+                    if let Some(frame) = self.receiver.receive(&buff[..n])? {
+                        return Ok(frame);
+                    }
+                }
+                _ => {
+                    return Err(Error::InvalidResponse(self.receiver.pending()));
+                }
+            }
+        }
+    }
+}
+
+fn io_error(e: impl embedded_io::Error) -> Error {
+    match e.kind() {
+        embedded_io::ErrorKind::TimedOut => Error::Timeout,
+        kind => Error::EmbeddedIo(kind),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{GetWifiMode, Test, WifiMode};
+    use alloc::vec::Vec;
+    use embedded_io::{ErrorKind, ErrorType};
+
+    /// A port that replays a fixed response and records what is written to it.
+    struct MemoryPort {
+        input: &'static [u8],
+        output: Vec<u8>,
+    }
+
+    impl MemoryPort {
+        fn new(input: &'static [u8]) -> Self {
+            Self {
+                input,
+                output: Vec::new(),
+            }
+        }
+    }
+
+    impl ErrorType for MemoryPort {
+        type Error = ErrorKind;
+    }
+
+    impl Read for MemoryPort {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, ErrorKind> {
+            // Return a few bytes at a time, like a serial port
+            let len = buf.len().min(self.input.len()).min(4);
+            buf[..len].copy_from_slice(&self.input[..len]);
+            self.input = &self.input[len..];
+            Ok(len)
+        }
+    }
+
+    impl Write for MemoryPort {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, ErrorKind> {
+            self.output.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), ErrorKind> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn sends_over_in_memory_port() {
+        let port = MemoryPort::new(b"AT+CWMODE?\r\n+CWMODE:2\r\n\r\nOK\r\n");
+        let mut interface = EmbeddedInterface::<_>::new(port);
+        assert_eq!(interface.send(GetWifiMode).unwrap(), WifiMode::ApMode);
+        assert_eq!(interface.into_inner().output, b"AT+CWMODE?\r\n");
+    }
+
+    #[test]
+    fn drops_rest_of_response_that_does_not_fit() {
+        let port = MemoryPort::new(
+            b"AT+CWMODE?\r\n\
+              +CWMODE:1\r\n+CWMODE:1\r\n+CWMODE:1\r\n+CWMODE:1\r\n\
+              +CWMODE:1\r\n+CWMODE:1\r\n+CWMODE:1\r\n+CWMODE:1\r\n\
+              +CWLAP:(3,\"a line that does not fit on its own\",-50)\r\n\
+              \r\nOK\r\n\
+              AT\r\n\r\nOK\r\n\
+              AT+CWMODE?\r\n+CWMODE:2\r\n\r\nOK\r\n",
+        );
+        let mut interface = EmbeddedInterface::<_, 32>::new(port);
+        assert!(matches!(
+            interface.send(GetWifiMode),
+            Err(Error::BufferFull)
+        ));
+        assert!(interface.send(Test).unwrap());
+        assert_eq!(interface.send(GetWifiMode).unwrap(), WifiMode::ApMode);
+    }
+
+    #[test]
+    fn reports_incomplete_response_when_port_closes() {
+        let port = MemoryPort::new(b"AT+CWMODE?\r\n+CWMO");
+        let mut interface = EmbeddedInterface::<_>::new(port);
+        match interface.send(GetWifiMode) {
+            Err(Error::InvalidResponse(pending)) => assert_eq!(pending, b"AT+CWMODE?\r\n+CWMO"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use crate::Outcome;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    #[cfg(feature = "serial")]
+    Serial(serialport::Error),
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    #[cfg(feature = "embedded")]
+    EmbeddedIo(embedded_io::ErrorKind),
+    /// No complete response was received in time.
+    Timeout,
+    /// The module is still busy with a previous command. The command can be retried later.
+    Busy(Busy),
+    /// The module responded with a final result code that is not a success.
+    Rejected(Rejection),
+    /// Connecting to an access point failed.
+    ConnectFailed(JoinFailure),
+    /// A field in the response could not be parsed.
+    Parse(ParseError),
+    /// The response is not valid UTF-8.
+    Utf8(core::str::Utf8Error),
+    /// The command could not be encoded.
+    Encode(Box<Error>),
+    Fmt(core::fmt::Error),
+    /// The response did not fit in the buffer of an `EmbeddedInterface`.
+    BufferFull,
+    /// The module did not respond on any of the baud rates that were tried.
+    #[cfg(feature = "serial")]
+    BaudRateNotDetected,
+    /// The connection was closed before a complete response was received.
+    InvalidResponse(Vec<u8>),
+    /// The firmware of the module does not support this command, such as `+CWMODE_CUR`.
+    Unsupported(&'static str),
+    /// An argument of the command has a value the command does not accept.
+    InvalidArgument(&'static str),
+}
+
+impl Error {
+    /// Create a [ParseError] for the given field in the given line.
+    pub(crate) fn parse(line: impl AsRef<[u8]>, field: &'static str) -> Error {
+        Error::Parse(ParseError {
+            line: line.as_ref().to_vec(),
+            field,
+        })
+    }
+
+    /// The error for a response with the given outcome, or `None` if the outcome is a success.
+    pub(crate) fn from_outcome(outcome: Outcome) -> Option<Error> {
+        Some(match outcome {
+            Outcome::Ok | Outcome::SendOk | Outcome::Prompt => return None,
+            Outcome::BusyProcessing => Error::Busy(Busy::Processing),
+            Outcome::BusySending => Error::Busy(Busy::Sending),
+            Outcome::Error(code) => Error::Rejected(Rejection::Error(code)),
+            Outcome::Fail => Error::Rejected(Rejection::Fail),
+            Outcome::SendFail => Error::Rejected(Rejection::SendFail),
+            Outcome::CmeError(code) => Error::Rejected(Rejection::CmeError(code)),
+            Outcome::CmsError(code) => Error::Rejected(Rejection::CmsError(code)),
+        })
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "serial")]
+            Error::Serial(e) => write!(f, "Serial port error: {}", e),
+            #[cfg(feature = "std")]
+            Error::Io(e) => write!(f, "IO error: {}", e),
+            #[cfg(feature = "embedded")]
+            Error::EmbeddedIo(kind) => write!(f, "IO error: {:?}", kind),
+            Error::Timeout => f.write_str("Timed out waiting for a response"),
+            Error::Busy(busy) => write!(f, "Module is busy {}", busy),
+            Error::Rejected(rejection) => write!(f, "Command rejected: {}", rejection),
+            Error::ConnectFailed(failure) => write!(f, "Could not connect to AP: {}", failure),
+            Error::Parse(e) => e.fmt(f),
+            Error::Utf8(e) => write!(f, "Response is not valid UTF-8: {}", e),
+            Error::Encode(e) => write!(f, "Could not encode command: {}", e),
+            Error::Fmt(e) => e.fmt(f),
+            Error::BufferFull => f.write_str("Response does not fit in the buffer"),
+            #[cfg(feature = "serial")]
+            Error::BaudRateNotDetected => f.write_str("Could not detect the baud rate"),
+            Error::InvalidResponse(response) => write!(
+                f,
+                "Incomplete response: {:?}",
+                alloc::string::String::from_utf8_lossy(response)
+            ),
+            Error::Unsupported(command) => {
+                write!(f, "The firmware does not support AT{}", command)
+            }
+            Error::InvalidArgument(reason) => write!(f, "Invalid argument: {}", reason),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "serial")]
+            Error::Serial(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Parse(e) => Some(e),
+            Error::Utf8(e) => Some(e),
+            Error::Encode(e) => Some(e.as_ref()),
+            Error::Fmt(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "serial")]
+impl From<serialport::Error> for Error {
+    fn from(e: serialport::Error) -> Error {
+        Error::Serial(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        if e.kind() == std::io::ErrorKind::TimedOut {
+            Error::Timeout
+        } else {
+            Error::Io(e)
+        }
+    }
+}
+
+impl From<core::fmt::Error> for Error {
+    fn from(e: core::fmt::Error) -> Error {
+        Error::Fmt(e)
+    }
+}
+
+impl From<core::str::Utf8Error> for Error {
+    fn from(e: core::str::Utf8Error) -> Error {
+        Error::Utf8(e)
+    }
+}
+
+/// What the module is busy with, see [Error::Busy].
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum Busy {
+    /// `busy p...`: still processing a previous command.
+    Processing,
+    /// `busy s...`: still sending data.
+    Sending,
+}
+
+impl fmt::Display for Busy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Busy::Processing => f.write_str("processing"),
+            Busy::Sending => f.write_str("sending"),
+        }
+    }
+}
+
+/// The final result code a command was rejected with, see [Error::Rejected].
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum Rejection {
+    /// `ERROR`, with the code from the `ERR CODE:0x...` line if the firmware sent one.
+    Error(Option<u32>),
+    /// `FAIL`
+    Fail,
+    /// `SEND FAIL`
+    SendFail,
+    /// `+CME ERROR: <n>`
+    CmeError(u32),
+    /// `+CMS ERROR: <n>`
+    CmsError(u32),
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rejection::Error(Some(code)) => write!(f, "ERROR (code 0x{:08x})", code),
+            Rejection::Error(None) => f.write_str("ERROR"),
+            Rejection::Fail => f.write_str("FAIL"),
+            Rejection::SendFail => f.write_str("SEND FAIL"),
+            Rejection::CmeError(code) => write!(f, "+CME ERROR: {}", code),
+            Rejection::CmsError(code) => write!(f, "+CMS ERROR: {}", code),
+        }
+    }
+}
+
+/// The reason connecting to an access point failed, as reported by `+CWJAP:<n>`.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum JoinFailure {
+    Timeout,
+    WrongPassword,
+    ApNotFound,
+    ConnectFailed,
+    Unknown(u8),
+}
+
+impl JoinFailure {
+    pub(crate) fn from_code(code: u8) -> JoinFailure {
+        match code {
+            1 => JoinFailure::Timeout,
+            2 => JoinFailure::WrongPassword,
+            3 => JoinFailure::ApNotFound,
+            4 => JoinFailure::ConnectFailed,
+            x => JoinFailure::Unknown(x),
+        }
+    }
+}
+
+impl fmt::Display for JoinFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JoinFailure::Timeout => f.write_str("connection timeout"),
+            JoinFailure::WrongPassword => f.write_str("wrong password"),
+            JoinFailure::ApNotFound => f.write_str("cannot find the target AP"),
+            JoinFailure::ConnectFailed => f.write_str("connection failed"),
+            JoinFailure::Unknown(code) => write!(f, "unknown error {}", code),
+        }
+    }
+}
+
+/// A field in a response that could not be parsed, see [Error::Parse].
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct ParseError {
+    /// The line that contains the field.
+    pub line: Vec<u8>,
+    /// The name of the field.
+    pub field: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid {} in {:?}",
+            self.field,
+            alloc::string::String::from_utf8_lossy(&self.line)
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
@@ -0,0 +1,284 @@
+//! Splitting the bytes received from the module into responses and unsolicited result codes.
+
+use crate::urc::{Ipd, Urc};
+use crate::Error;
+use core::ops::Range;
+
+/// The final result code that ends the response to a command.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum Outcome {
+    /// `OK`
+    Ok,
+    /// `ERROR`, optionally preceded by an `ERR CODE:0x...` line on ESP-AT firmware.
+    Error(Option<u32>),
+    /// `FAIL`
+    Fail,
+    /// `SEND OK`
+    SendOk,
+    /// `SEND FAIL`
+    SendFail,
+    /// `busy p...`: the module is still processing a previous command.
+    BusyProcessing,
+    /// `busy s...`: the module is still sending data.
+    BusySending,
+    /// `+CME ERROR: <n>`
+    CmeError(u32),
+    /// `+CMS ERROR: <n>`
+    CmsError(u32),
+    /// `>`: the module is waiting for data to be sent.
+    ///
+    /// Commands such as `AT+CIPSEND` send `OK` before the prompt, so they need to return `true` from [Command::expects_prompt](crate::Command::expects_prompt) to receive it.
+    Prompt,
+}
+
+impl Outcome {
+    /// Returns `true` if this outcome means the command succeeded.
+    pub fn is_success(self) -> bool {
+        matches!(self, Outcome::Ok | Outcome::SendOk | Outcome::Prompt)
+    }
+
+    fn parse(line: &[u8], err_code: Option<u32>) -> Option<Outcome> {
+        Some(match line {
+            b"OK" => Outcome::Ok,
+            b"ERROR" => Outcome::Error(err_code),
+            b"FAIL" => Outcome::Fail,
+            b"SEND OK" => Outcome::SendOk,
+            b"SEND FAIL" => Outcome::SendFail,
+            _ if line.starts_with(b"busy p") => Outcome::BusyProcessing,
+            _ if line.starts_with(b"busy s") => Outcome::BusySending,
+            _ if line.starts_with(b"+CME ERROR:") => {
+                Outcome::CmeError(parse_number(&line[b"+CME ERROR:".len()..], 10)?)
+            }
+            _ if line.starts_with(b"+CMS ERROR:") => {
+                Outcome::CmsError(parse_number(&line[b"+CMS ERROR:".len()..], 10)?)
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// A complete response to a command, as passed to [Command::decode_response](crate::Command::decode_response).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Response<'a> {
+    /// Everything the module sent before the final result code, including the echo of the command.
+    pub body: &'a [u8],
+    pub outcome: Outcome,
+}
+
+impl<'a> Response<'a> {
+    /// Returns the body if the outcome is a success, or the matching [Error] otherwise.
+    pub fn result(&self) -> Result<&'a [u8], Error> {
+        match Error::from_outcome(self.outcome) {
+            Some(e) => Err(e),
+            None => Ok(self.body),
+        }
+    }
+}
+
+/// A response that was found by the [Framer].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Frame {
+    /// The length of the body, excluding the newline before the final result code.
+    pub body_len: usize,
+    /// The length of the response, including the final result code.
+    pub len: usize,
+    pub outcome: Outcome,
+}
+
+/// Something that was found by the [Framer].
+pub(crate) enum Event {
+    Response(Frame),
+    /// An unsolicited result code, which takes up the bytes in the given range. These bytes should be removed from the buffer before the next call to [Framer::feed].
+    Urc(Urc, Range<usize>),
+}
+
+/// Line-oriented state machine that finds the final result code and unsolicited result codes in the received bytes.
+///
+/// The bytes are kept in a buffer owned by the interface, which is passed to [Framer::feed] every time new data is received.
+#[derive(Default)]
+pub(crate) struct Framer {
+    line_start: usize,
+    err_code: Option<u32>,
+    /// Whether `OK` is followed by the data prompt, which ends the response instead.
+    expect_prompt: bool,
+}
+
+impl Framer {
+    /// Scan the lines in `buffer` that have not been scanned before.
+    ///
+    /// Returns a response once its final result code is received. The framer is then reset, and the bytes of the frame should be removed from the buffer before the next call.
+    pub fn feed(&mut self, buffer: &[u8]) -> Option<Event> {
+        loop {
+            let rest = &buffer[self.line_start..];
+            if rest.starts_with(b"+IPD,") {
+                match Urc::parse_ipd(rest) {
+                    Ipd::Complete(urc, len) => {
+                        return Some(Event::Urc(urc, self.line_start..self.line_start + len));
+                    }
+                    Ipd::Incomplete => return None,
+                    Ipd::Invalid => {}
+                }
+            }
+            let newline = match rest.iter().position(|b| *b == b'\n') {
+                Some(newline) => newline,
+                None => {
+                    // The data prompt is not followed by a newline
+                    if rest == b">" || rest == b"> " {
+                        return Some(Event::Response(self.finish(
+                            buffer,
+                            buffer.len(),
+                            Outcome::Prompt,
+                        )));
+                    }
+                    return None;
+                }
+            };
+            let line = trim_line(&rest[..newline]);
+            let line_end = self.line_start + newline + 1;
+
+            if let Some(outcome) = Outcome::parse(line, self.err_code) {
+                if !(self.expect_prompt && outcome == Outcome::Ok) {
+                    return Some(Event::Response(self.finish(buffer, line_end, outcome)));
+                }
+            }
+            if let Some(urc) = Urc::parse(line) {
+                return Some(Event::Urc(urc, self.line_start..line_end));
+            }
+            if let Some(code) = line.strip_prefix(b"ERR CODE:0x") {
+                self.err_code = parse_number(code, 16);
+            }
+            self.line_start = line_end;
+        }
+    }
+
+    /// The position up to which the buffer has been scanned. Everything before this is part of an unfinished response.
+    pub fn scanned(&self) -> usize {
+        self.line_start
+    }
+
+    /// Forget the lines that were scanned, so they can be removed from the buffer. The unfinished response is still scanned up to its final result code. Returns the amount of bytes that were scanned.
+    pub fn drop_scanned(&mut self) -> usize {
+        core::mem::take(&mut self.line_start)
+    }
+
+    /// Keep scanning after `OK` until the data prompt is received. This is cleared by [reset](Framer::reset).
+    pub fn expect_prompt(&mut self, expect_prompt: bool) {
+        self.expect_prompt = expect_prompt;
+    }
+
+    /// Forget the unfinished response.
+    pub fn reset(&mut self) {
+        *self = Framer::default();
+    }
+
+    fn finish(&mut self, buffer: &[u8], len: usize, outcome: Outcome) -> Frame {
+        // The final result code is preceded by an empty line, which is not part of the body
+        let body = &buffer[..self.line_start];
+        let body = body.strip_suffix(b"\n").unwrap_or(body);
+        let body = body.strip_suffix(b"\r").unwrap_or(body);
+
+        self.reset();
+        Frame {
+            body_len: body.len(),
+            len,
+            outcome,
+        }
+    }
+}
+
+fn trim_line(line: &[u8]) -> &[u8] {
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+fn parse_number(digits: &[u8], radix: u32) -> Option<u32> {
+    let digits = core::str::from_utf8(digits).ok()?;
+    u32::from_str_radix(digits.trim(), radix).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(framer: &mut Framer, input: &[u8]) -> Option<Frame> {
+        match framer.feed(input) {
+            Some(Event::Response(frame)) => Some(frame),
+            _ => None,
+        }
+    }
+
+    fn outcome(input: &[u8]) -> Option<Outcome> {
+        frame(&mut Framer::default(), input).map(|frame| frame.outcome)
+    }
+
+    #[test]
+    fn recognizes_every_final_result_code() {
+        let cases: [(&[u8], Outcome); 9] = [
+            (b"AT\r\n\r\nOK\r\n", Outcome::Ok),
+            (b"AT+X\r\n\r\nERROR\r\n", Outcome::Error(None)),
+            (b"AT+CWJAP\r\n+CWJAP:1\r\n\r\nFAIL\r\n", Outcome::Fail),
+            (b"Recv 5 bytes\r\n\r\nSEND OK\r\n", Outcome::SendOk),
+            (b"Recv 5 bytes\r\n\r\nSEND FAIL\r\n", Outcome::SendFail),
+            (b"AT\r\nbusy p...\r\n", Outcome::BusyProcessing),
+            (b"AT\r\nbusy s...\r\n", Outcome::BusySending),
+            (b"AT+X\r\n+CME ERROR: 12\r\n", Outcome::CmeError(12)),
+            (b"AT+X\r\n+CMS ERROR: 500\r\n", Outcome::CmsError(500)),
+        ];
+        for (input, expected) in cases.iter() {
+            assert_eq!(outcome(input), Some(*expected), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn attaches_err_code_to_error() {
+        assert_eq!(
+            outcome(b"AT+X\r\n\r\nERR CODE:0x01090000\r\nERROR\r\n"),
+            Some(Outcome::Error(Some(0x0109_0000)))
+        );
+    }
+
+    #[test]
+    fn waits_for_final_result_code() {
+        assert_eq!(outcome(b"AT+CWMODE?\r\n+CWMODE:1\r\n"), None);
+        assert_eq!(outcome(b"AT+CWMODE?\r\n+CWMODE:1\r\n\r\nOK"), None);
+        assert_eq!(outcome(b"AT+X\r\n+CME ERROR: x\r\n"), None);
+    }
+
+    #[test]
+    fn excludes_empty_line_from_body() {
+        let input = b"AT+CWMODE?\r\n+CWMODE:1\r\n\r\nOK\r\nWIFI";
+        let frame = frame(&mut Framer::default(), input).unwrap();
+        assert_eq!(&input[..frame.body_len], b"AT+CWMODE?\r\n+CWMODE:1\r\n");
+        assert_eq!(frame.len, input.len() - b"WIFI".len());
+    }
+
+    #[test]
+    fn recognizes_prompt() {
+        assert_eq!(outcome(b"AT+CIPSEND=5\r\n\r\n>"), Some(Outcome::Prompt));
+        assert_eq!(outcome(b"AT+CIPSEND=5\r\n\r\n> "), Some(Outcome::Prompt));
+    }
+
+    #[test]
+    fn waits_for_prompt_after_ok() {
+        let input = b"AT+CIPSEND=5\r\n\r\nOK\r\n\r\n>";
+        assert_eq!(outcome(input), Some(Outcome::Ok));
+
+        let mut framer = Framer::default();
+        framer.expect_prompt(true);
+        assert_eq!(frame(&mut framer, &input[..input.len() - 1]), None);
+        let frame = frame(&mut framer, input).unwrap();
+        assert_eq!(frame.outcome, Outcome::Prompt);
+        assert_eq!(frame.len, input.len());
+
+        let mut framer = Framer::default();
+        framer.expect_prompt(true);
+        assert_eq!(
+            framer
+                .feed(b"AT+CIPSEND=5\r\n\r\nERROR\r\n")
+                .map(|event| match event {
+                    Event::Response(frame) => frame.outcome,
+                    Event::Urc(..) => panic!("unexpected URC"),
+                }),
+            Some(Outcome::Error(None))
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use crate::framer::Frame;
+use crate::receiver::Receiver;
+use crate::{encode_command, Command, Error, RetryPolicy, Transport, Urc};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "serial")]
+use crate::{InterfaceBuilder, COMMON_BAUD_RATES};
+#[cfg(feature = "serial")]
+use serialport::SerialPort;
+
+#[cfg(feature = "serial")]
+pub struct Interface<T: Transport = Box<dyn SerialPort>> {
+    pub(crate) port: T,
+    pub(crate) receiver: Receiver<Vec<u8>>,
+    timeout: Duration,
+    retry_policy: RetryPolicy,
+}
+
+#[cfg(not(feature = "serial"))]
+pub struct Interface<T: Transport> {
+    pub(crate) port: T,
+    pub(crate) receiver: Receiver<Vec<u8>>,
+    timeout: Duration,
+    retry_policy: RetryPolicy,
+}
+
+#[cfg(feature = "serial")]
+impl Interface {
+    /// Open the given serial port with the default settings of [InterfaceBuilder].
+    pub fn new(port: &str) -> Result<Self, Error> {
+        Self::builder(port).open()
+    }
+
+    /// Open the given serial port and find the baud rate the module responds on, trying [COMMON_BAUD_RATES].
+    ///
+    /// Returns the interface together with the detected baud rate. Use [InterfaceBuilder::autodetect] to change the other port settings or the rates that are tried.
+    pub fn autodetect(port: &str) -> Result<(Self, u32), Error> {
+        Self::builder(port).autodetect(COMMON_BAUD_RATES)
+    }
+
+    /// Configure a serial port before opening it.
+    pub fn builder(port: &str) -> InterfaceBuilder<'_> {
+        InterfaceBuilder::new(port)
+    }
+}
+
+impl<T: Transport> Interface<T> {
+    /// Create an interface that talks over the given transport, with a default timeout of 30 seconds.
+    pub fn with_transport(port: T) -> Result<Self, Error> {
+        Ok(Self::from_port(port))
+    }
+
+    pub(crate) fn from_port(port: T) -> Self {
+        Self {
+            port,
+            receiver: Receiver::new(Vec::new()),
+            timeout: Duration::from_secs(30),
+            retry_policy: RetryPolicy::NONE,
+        }
+    }
+
+    /// Set how long a command may take if it does not have its own [timeout](Command::timeout).
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Set when failed commands are retried.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// Set the callback that receives the [Urc]s sent by the module.
+    ///
+    /// URCs are only read while a command is being sent. They are not part of the response that is passed to the command.
+    pub fn set_urc_handler(&mut self, handler: impl FnMut(Urc) + Send + 'static) {
+        self.receiver.set_urc_handler(Box::new(handler));
+    }
+
+    /// Consume the interface, returning the underlying transport.
+    pub fn into_transport(self) -> T {
+        self.port
+    }
+
+    pub fn send<C: Command>(&mut self, command: C) -> Result<C::Output, Error> {
+        let mut retries = 0;
+        loop {
+            match self.send_once(&command) {
+                Err(e) if self.retry_policy.should_retry(&e, retries) => {
+                    std::thread::sleep(self.retry_policy.backoff(retries));
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn send_once<C: Command>(&mut self, command: &C) -> Result<C::Output, Error> {
+        let mut line = String::new();
+        encode_command(command, &mut line)?;
+        self.receiver.start(&line, command.expects_prompt());
+        if let Err(e) = self.port.write_all(line.as_bytes()) {
+            self.receiver.cancel();
+            return Err(e.into());
+        }
+
+        match self.receive(command.timeout().unwrap_or(self.timeout)) {
+            Ok(frame) => self.receiver.decode(command, frame),
+            Err(Error::Timeout) => self.receiver.time_out(command),
+            Err(e) => {
+                self.receiver.cancel();
+                Err(e)
+            }
+        }
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<Frame, Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(Error::Timeout);
+            }
+            self.port.set_timeout(remaining)?;
+
+            let mut buff = [0u8; 1024];
+            match self.port.read(&mut buff)? {
+                n if n > 0 => {
+                    if let Some(frame) = self.receiver.receive(&buff[..n])? {
+                        return Ok(frame);
+                    }
+                }
+                _ => {
+                    return Err(Error::InvalidResponse(self.receiver.pending()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::command::{GetWifiMode, Test, WifiMode};
+    use std::collections::VecDeque;
+    use std::io::{self, Read, Write};
+
+    /// A transport that replays fixed responses and records what is written to it.
+    pub(crate) struct MemoryTransport {
+        /// The bytes that are read, where `None` makes a read time out.
+        input: VecDeque<Option<Vec<u8>>>,
+        pub(crate) output: Vec<u8>,
+    }
+
+    impl MemoryTransport {
+        fn new(input: &[u8]) -> Self {
+            Self::with_reads(&[Some(input)])
+        }
+
+        pub(crate) fn with_reads(reads: &[Option<&[u8]>]) -> Self {
+            Self {
+                input: reads.iter().map(|read| read.map(<[u8]>::to_vec)).collect(),
+                output: Vec::new(),
+            }
+        }
+    }
+
+    impl Read for MemoryTransport {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let chunk = match self.input.front_mut() {
+                Some(Some(chunk)) => chunk,
+                Some(None) => {
+                    self.input.pop_front();
+                    return Err(io::ErrorKind::TimedOut.into());
+                }
+                None => return Ok(0),
+            };
+            // Return a few bytes at a time, like a serial port
+            let len = buf.len().min(chunk.len()).min(4);
+            buf[..len].copy_from_slice(&chunk[..len]);
+            chunk.drain(..len);
+            if chunk.is_empty() {
+                self.input.pop_front();
+            }
+            Ok(len)
+        }
+    }
+
+    impl Write for MemoryTransport {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Transport for MemoryTransport {
+        fn set_timeout(&mut self, _timeout: Duration) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn sends_over_in_memory_transport() {
+        let transport = MemoryTransport::new(b"AT+CWMODE?\r\n+CWMODE:2\r\n\r\nOK\r\n");
+        let mut interface = Interface::with_transport(transport).unwrap();
+        assert_eq!(interface.send(GetWifiMode).unwrap(), WifiMode::ApMode);
+        assert_eq!(interface.into_transport().output, b"AT+CWMODE?\r\n");
+    }
+
+    #[test]
+    fn reports_incomplete_response_when_transport_closes() {
+        let transport = MemoryTransport::new(b"AT+CWMODE?\r\n+CWMO");
+        let mut interface = Interface::with_transport(transport).unwrap();
+        match interface.send(GetWifiMode) {
+            Err(Error::InvalidResponse(pending)) => assert_eq!(pending, b"AT+CWMODE?\r\n+CWMO"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn drops_late_response_to_command_that_timed_out() {
+        let transport = MemoryTransport::with_reads(&[
+            None,
+            Some(b"AT\r\n\r\nOK\r\nAT+CWMODE?\r\n+CWMODE:2\r\n\r\nOK\r\n"),
+        ]);
+        let mut interface = Interface::with_transport(transport).unwrap();
+        assert!(matches!(interface.send(Test), Err(Error::Timeout)));
+        assert_eq!(interface.send(GetWifiMode).unwrap(), WifiMode::ApMode);
+    }
+}
@@ -1,86 +1,118 @@
-use serialport::SerialPort;
-use std::time::Duration;
+//! Send AT commands to ESP8266/ESP32 modules.
+//!
+//! The `std` and `serial` features are enabled by default. Without `std` the crate is `no_std`, and `EmbeddedInterface` can be used with the `embedded` feature.
+//!
+//! The crate always needs `alloc`, so a `no_std` target needs a global allocator. Received bytes and encoded commands are kept in fixed buffers, but the outputs of commands (such as the `Vec` of access points), [Error], [Urc] and the URC handler are allocated.
+//!
+//! All traffic is logged through the [log] crate at debug level, on the `at_protocol::tx` and `at_protocol::rx` targets.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+// Lets the derive macros refer to `::at_protocol` inside this crate too
+extern crate self as at_protocol;
+
+#[cfg(feature = "tokio")]
+mod async_interface;
+#[cfg(feature = "serial")]
+mod builder;
+mod capabilities;
 pub mod command;
+#[cfg(feature = "embedded")]
+mod embedded_interface;
+mod error;
+#[cfg_attr(not(any(feature = "std", feature = "embedded")), allow(dead_code))]
+mod framer;
+#[cfg(feature = "std")]
+mod interface;
+#[cfg_attr(not(any(feature = "std", feature = "embedded")), allow(dead_code))]
+mod receiver;
+mod retry;
+#[cfg(feature = "std")]
+mod shared_interface;
+mod trace;
+#[cfg(feature = "std")]
+mod transport;
+mod urc;
 
-pub struct Interface {
-    port: Box<dyn SerialPort>,
-}
+#[cfg(feature = "tokio")]
+pub use self::async_interface::AsyncInterface;
+#[cfg(feature = "serial")]
+pub use self::builder::{ControlLine, InterfaceBuilder, LineToggle, COMMON_BAUD_RATES};
+pub use self::capabilities::{Capabilities, PersistenceStyle};
+#[cfg(feature = "embedded")]
+pub use self::embedded_interface::EmbeddedInterface;
+pub use self::error::{Busy, Error, JoinFailure, ParseError, Rejection};
+pub use self::framer::{Outcome, Response};
+#[cfg(feature = "std")]
+pub use self::interface::Interface;
+pub use self::retry::RetryPolicy;
+#[cfg(feature = "std")]
+pub use self::shared_interface::SharedInterface;
+#[cfg(feature = "std")]
+pub use self::transport::Transport;
+pub use self::urc::Urc;
+pub use at_protocol_derive::{AtCommand, AtResponse};
+#[cfg(feature = "serial")]
+pub use serialport::{DataBits, FlowControl, Parity, StopBits};
 
-impl Interface {
-    pub fn new(port: &str) -> Result<Self, Error> {
-        let port = serialport::new(port, 115200)
-            .timeout(Duration::from_secs(30))
-            .open()?;
+use alloc::boxed::Box;
 
-        Ok(Self { port })
-    }
+/// Encode a command into `buffer`, ready to be written to the module.
+#[cfg_attr(not(any(feature = "std", feature = "embedded")), allow(dead_code))]
+fn encode_command<C: Command, B: core::fmt::Write + AsRef<str>>(
+    command: &C,
+    buffer: &mut B,
+) -> Result<(), Error> {
+    command
+        .encode(buffer)
+        .map_err(|e| Error::Encode(Box::new(e)))?;
 
-    pub fn send<T: Command>(&mut self, command: T) -> Result<T::Output, Error> {
-        const END_PHRASE: &[u8] = b"\r\nOK\r\n";
-        const ERROR_PHRASE: &[u8] = b"\r\nERROR\r\n";
-        let mut buffer = Vec::new();
-        command
-            .encode(&mut buffer)
-            .map_err(|e| Error::Encode(Box::new(e)))?;
-
-        if cfg!(debug_assertions) && !buffer.ends_with(b"\r\n") {
-            panic!("Command should end with \r\n");
-        }
-
-        println!("> {:?}", std::str::from_utf8(&buffer).unwrap().trim());
-        self.port.write_all(&buffer)?;
-
-        buffer.clear();
-        'receive_loop: loop {
-            let mut buff = [0u8; 1024];
-            match self.port.read(&mut buff)? {
-                n if n > 0 => {
-                    buffer.extend_from_slice(&buff[..n]);
-                    if buffer.ends_with(END_PHRASE) {
-                        buffer.drain(buffer.len() - END_PHRASE.len()..);
-                        println!("< {:?}", std::str::from_utf8(&buffer).unwrap().trim());
-
-                        break 'receive_loop;
-                    }
-                    if buffer.ends_with(ERROR_PHRASE) {
-                        return Err(Error::Custom(String::from_utf8(buffer).unwrap()));
-                    }
-                }
-                _ => {
-                    return Err(Error::InvalidResponse(buffer));
-                }
-            }
-        }
-
-        command.decode(&buffer)
+    if cfg!(debug_assertions) && !buffer.as_ref().ends_with("\r\n") {
+        panic!("Command should end with \r\n");
     }
+
+    trace::transmit(command);
+    Ok(())
 }
 
 pub trait Command {
     type Output;
 
-    fn encode(&self, output: &mut impl std::io::Write) -> Result<(), Error>;
+    fn encode(&self, output: &mut impl core::fmt::Write) -> Result<(), Error>;
+
+    /// Encode the command for logging, with secrets such as passwords replaced. By default this is the same as [encode](Command::encode).
+    fn encode_redacted(&self, output: &mut impl core::fmt::Write) -> Result<(), Error> {
+        self.encode(output)
+    }
+
     fn decode(&self, input: &[u8]) -> Result<Self::Output, Error>;
-}
 
-#[derive(Debug)]
-pub enum Error {
-    Serial(serialport::Error),
-    Io(std::io::Error),
-    Encode(Box<Error>),
-    Custom(String),
-    InvalidResponse(Vec<u8>),
-}
+    /// Write a line of the response for logging, with secrets such as passwords replaced. By default the line is written unchanged.
+    ///
+    /// The echo of the command is not passed to this, it is logged with [encode_redacted](Command::encode_redacted) instead.
+    fn redact_response_line(
+        &self,
+        line: &str,
+        output: &mut impl core::fmt::Write,
+    ) -> Result<(), Error> {
+        output.write_str(line).map_err(Into::into)
+    }
 
-impl From<serialport::Error> for Error {
-    fn from(e: serialport::Error) -> Error {
-        Error::Serial(e)
+    /// How long this command may take before it times out. If this is `None`, the default timeout of the interface is used.
+    fn timeout(&self) -> Option<core::time::Duration> {
+        None
+    }
+
+    /// Whether the module sends the data prompt `>` after `OK`, such as for `AT+CIPSEND`. The response then only ends at the prompt, with [Outcome::Prompt].
+    fn expects_prompt(&self) -> bool {
+        false
     }
-}
 
-impl From<std::io::Error> for Error {
-    fn from(e: std::io::Error) -> Error {
-        Error::Io(e)
+    /// Decode the complete response of the module.
+    ///
+    /// By default this fails with [Error::Busy] or [Error::Rejected] if the outcome is not a success, and passes the body to [decode](Command::decode) otherwise. Commands that report details on failure can override this.
+    fn decode_response(&self, response: &Response) -> Result<Self::Output, Error> {
+        self.decode(response.result()?)
     }
 }
@@ -25,12 +25,9 @@ fn main() {
         println!("Ok!");
     }
 
-    interface
-        .send(command::ConnectToAp {
-            ssid: "",
-            password: "",
-        })
-        .unwrap();
-
-    println!("Connected to AP");
+    match interface.send(command::ConnectToAp::new("", "")) {
+        Ok(()) => println!("Connected to AP"),
+        Err(Error::ConnectFailed(reason)) => println!("Could not connect to AP: {}", reason),
+        Err(e) => panic!("{}", e),
+    }
 }
@@ -0,0 +1,437 @@
+use crate::framer::{Event, Frame, Framer};
+use crate::{trace, Command, Error, Response, Urc};
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// How many commands that timed out are remembered, so their late responses can be dropped.
+const MAX_STALE: usize = 8;
+
+/// A callback that receives the [Urc]s of an interface.
+pub(crate) type UrcHandler = Box<dyn FnMut(Urc) + Send>;
+
+/// Storage for the bytes received from the module.
+pub(crate) trait Buffer {
+    fn as_slice(&self) -> &[u8];
+    fn extend(&mut self, bytes: &[u8]) -> Result<(), Error>;
+    fn remove(&mut self, range: Range<usize>);
+}
+
+impl Buffer for Vec<u8> {
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
+
+    fn extend(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn remove(&mut self, range: Range<usize>) {
+        self.drain(range);
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl<const N: usize> Buffer for heapless::Vec<u8, N> {
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
+
+    fn extend(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.extend_from_slice(bytes)
+            .map_err(|()| Error::BufferFull)
+    }
+
+    fn remove(&mut self, range: Range<usize>) {
+        let len = self.len() - range.len();
+        self.copy_within(range.end.., range.start);
+        self.truncate(len);
+    }
+}
+
+/// How a response relates to the command that is waiting for one, see [Receiver::judge].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Verdict {
+    /// The response answers the waiting command.
+    Accept,
+    /// The response answers a command that timed out before, and is dropped.
+    Stale,
+    /// The response answers either the waiting command or an earlier attempt at the same command that timed out. It is held, and used if no other response arrives before the command times out.
+    Hold,
+}
+
+/// The receiving half of an interface. This frames the received bytes, and separates the responses from the URCs.
+///
+/// A response that arrives after its command timed out is dropped, so it is not mistaken for the response to the next command.
+pub(crate) struct Receiver<B> {
+    buffer: B,
+    framer: Framer,
+    urc_handler: Option<UrcHandler>,
+    /// The command that is waiting for a response, without the trailing newline.
+    pending: Option<String>,
+    /// The commands that timed out and may still be answered, oldest first.
+    stale: VecDeque<String>,
+    /// A response that was held back, see [Verdict::Hold].
+    held: Option<(Vec<u8>, Frame)>,
+    /// Set when a response did not fit in the buffer, to how it was judged. The rest of that response is dropped up to its final result code.
+    overflow: Option<Verdict>,
+}
+
+impl<B: Buffer> Receiver<B> {
+    pub fn new(buffer: B) -> Self {
+        Self {
+            buffer,
+            framer: Framer::default(),
+            urc_handler: None,
+            pending: None,
+            stale: VecDeque::new(),
+            held: None,
+            overflow: None,
+        }
+    }
+
+    pub fn set_urc_handler(&mut self, handler: UrcHandler) {
+        self.urc_handler = Some(handler);
+    }
+
+    /// Prepare for a new command: dispatch the URCs that are left in the buffer, and drop anything else that was received since the last response.
+    ///
+    /// An incomplete line at the end of the buffer is kept, as it could be the start of a URC.
+    pub fn clear(&mut self) {
+        self.pending = None;
+        while let Some(frame) = self.next_frame() {
+            if self.overflow.take().is_none() {
+                self.judge(frame.body_len);
+            }
+            trace::discard(&self.buffer.as_slice()[..frame.len]);
+            self.buffer.remove(0..frame.len);
+        }
+        if self.overflow.is_some() {
+            // The rest of a response that did not fit is still dropped, but it no longer fails the command
+            self.overflow = Some(Verdict::Stale);
+        } else {
+            self.buffer.remove(0..self.framer.scanned());
+            self.framer.reset();
+        }
+    }
+
+    /// Prepare for sending the command encoded as `line`, see [clear](Receiver::clear). Its response is then told apart from late responses to commands that timed out.
+    ///
+    /// `expect_prompt` is whether the response ends with the data prompt after `OK`, see [Command::expects_prompt].
+    pub fn start(&mut self, line: &str, expect_prompt: bool) {
+        self.clear();
+        self.framer.expect_prompt(expect_prompt);
+        self.pending = Some(line.trim_end().to_string());
+        self.held = None;
+    }
+
+    /// Forget the command that is waiting for a response, because it could not be sent.
+    pub fn cancel(&mut self) {
+        self.pending = None;
+        self.held = None;
+    }
+
+    /// Give up waiting for the response to the current command.
+    ///
+    /// If a response was held back because it could also answer an earlier attempt at the same command, it is passed to the command, as no other response arrived. Otherwise this fails with [Error::Timeout], and the command is remembered so its late response is dropped.
+    pub fn time_out<C: Command>(&mut self, command: &C) -> Result<C::Output, Error> {
+        let pending = self.pending.take();
+        if let Some((bytes, frame)) = self.held.take() {
+            return decode(command, &bytes, frame);
+        }
+        if let Some(line) = pending {
+            if self.stale.len() == MAX_STALE {
+                self.stale.pop_front();
+            }
+            self.stale.push_back(line);
+        }
+        Err(Error::Timeout)
+    }
+
+    /// Add received bytes to the buffer. Returns the response to the current command once it is complete.
+    ///
+    /// If the response does not fit in the buffer, the rest of it is dropped, and this fails with [Error::BufferFull] once its final result code is received.
+    pub fn receive(&mut self, bytes: &[u8]) -> Result<Option<Frame>, Error> {
+        if self.buffer.extend(bytes).is_err() {
+            self.overflow();
+            self.buffer.extend(bytes)?;
+        }
+        self.next_response()
+    }
+
+    /// Make room in a full buffer by dropping the lines of the unfinished response.
+    fn overflow(&mut self) {
+        if self.overflow.is_none() {
+            let verdict = self.judge(self.framer.scanned());
+            if verdict == Verdict::Accept {
+                self.pending = None;
+            }
+            self.overflow = Some(verdict);
+        }
+        let scanned = self.framer.drop_scanned();
+        trace::discard(&self.buffer.as_slice()[..scanned]);
+        self.buffer.remove(0..scanned);
+        if scanned == 0 {
+            // A single line does not fit
+            let len = self.buffer.as_slice().len();
+            trace::discard(self.buffer.as_slice());
+            self.buffer.remove(0..len);
+        }
+    }
+
+    /// Add received bytes to the buffer, without looking for a response. Use [next_response](Receiver::next_response) to find the responses in them.
+    #[cfg(feature = "std")]
+    pub fn extend(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.buffer.extend(bytes)
+    }
+
+    /// Pass a complete response to the command, and remove it from the buffer.
+    pub fn decode<C: Command>(&mut self, command: &C, frame: Frame) -> Result<C::Output, Error> {
+        let buffer = self.buffer.as_slice();
+        let result = decode(command, buffer, frame);
+        self.buffer.remove(0..frame.len);
+        result
+    }
+
+    /// Remove a complete response from the buffer, returning its bytes.
+    pub fn take(&mut self, frame: Frame) -> Vec<u8> {
+        let bytes = self.buffer.as_slice()[..frame.len].to_vec();
+        self.buffer.remove(0..frame.len);
+        bytes
+    }
+
+    /// The bytes of the unfinished response.
+    pub fn pending(&self) -> Vec<u8> {
+        self.buffer.as_slice().to_vec()
+    }
+
+    /// Find the next response in the buffer that answers the waiting command, dropping the late responses to commands that timed out. The response stays in the buffer until it is removed.
+    ///
+    /// Fails with [Error::BufferFull] at the end of a response to the waiting command that did not fit in the buffer.
+    pub fn next_response(&mut self) -> Result<Option<Frame>, Error> {
+        while let Some(frame) = self.next_frame() {
+            if let Some(verdict) = self.overflow.take() {
+                trace::discard(&self.buffer.as_slice()[..frame.len]);
+                self.buffer.remove(0..frame.len);
+                if verdict == Verdict::Accept {
+                    return Err(Error::BufferFull);
+                }
+                continue;
+            }
+            match self.judge(frame.body_len) {
+                Verdict::Accept => {
+                    self.pending = None;
+                    self.held = None;
+                    return Ok(Some(frame));
+                }
+                Verdict::Stale => {
+                    trace::discard(&self.buffer.as_slice()[..frame.len]);
+                    self.buffer.remove(0..frame.len);
+                }
+                Verdict::Hold => self.held = Some((self.take(frame), frame)),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Decide whether a response with a body of `body_len` bytes answers the waiting command.
+    ///
+    /// The module answers commands in order, so a response belongs to the oldest command that timed out, unless its echo shows it belongs to the waiting command.
+    fn judge(&mut self, body_len: usize) -> Verdict {
+        let echo = self.buffer.as_slice()[..body_len]
+            .split(|b| *b == b'\n')
+            .next()
+            .unwrap_or_default()
+            .trim_ascii();
+        let pending = match &self.pending {
+            Some(pending) => pending.as_bytes(),
+            None => {
+                self.stale.pop_front();
+                return Verdict::Stale;
+            }
+        };
+        if echo.starts_with(b"AT") && echo != pending {
+            // The echo of another command, so the commands that timed out before it will not be answered anymore
+            if let Some(index) = self.stale.iter().position(|stale| stale.as_bytes() == echo) {
+                self.stale.drain(..=index);
+            }
+            return Verdict::Stale;
+        }
+        let retried = self
+            .stale
+            .front()
+            .is_some_and(|stale| stale.as_bytes() == pending);
+        if echo == pending && !retried {
+            // The commands that timed out before will not be answered anymore
+            self.stale.clear();
+            return Verdict::Accept;
+        }
+        match self.stale.pop_front() {
+            // An earlier attempt at the same command may have been lost, in which case this answers the waiting command
+            Some(_) if retried => Verdict::Hold,
+            Some(_) => Verdict::Stale,
+            None => Verdict::Accept,
+        }
+    }
+
+    /// Find the next complete response in the buffer, passing the URCs before it to the handler. The response stays in the buffer until it is removed.
+    fn next_frame(&mut self) -> Option<Frame> {
+        while let Some(event) = self.framer.feed(self.buffer.as_slice()) {
+            match event {
+                Event::Response(frame) => return Some(frame),
+                Event::Urc(urc, range) => {
+                    trace::urc(&self.buffer.as_slice()[range.clone()]);
+                    self.buffer.remove(range);
+                    if urc == Urc::Ready {
+                        // The module restarted, so the commands that timed out will not be answered anymore
+                        self.stale.clear();
+                    }
+                    if let Some(handler) = &mut self.urc_handler {
+                        handler(urc);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Pass a complete response to the command.
+pub(crate) fn decode<C: Command>(
+    command: &C,
+    bytes: &[u8],
+    frame: Frame,
+) -> Result<C::Output, Error> {
+    trace::response(command, &bytes[..frame.len]);
+    command.decode_response(&Response {
+        body: &bytes[..frame.body_len],
+        outcome: frame.outcome,
+    })
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::command::Test;
+    use crate::Outcome;
+    use alloc::sync::Arc;
+    use std::sync::Mutex;
+
+    fn receiver() -> (Receiver<Vec<u8>>, Arc<Mutex<Vec<Urc>>>) {
+        let urcs = Arc::new(Mutex::new(Vec::new()));
+        let mut receiver = Receiver::new(Vec::new());
+        let handler_urcs = urcs.clone();
+        receiver.set_urc_handler(Box::new(move |urc| handler_urcs.lock().unwrap().push(urc)));
+        (receiver, urcs)
+    }
+
+    fn body(receiver: &mut Receiver<Vec<u8>>, frame: Frame) -> Vec<u8> {
+        let mut bytes = receiver.take(frame);
+        bytes.truncate(frame.body_len);
+        bytes
+    }
+
+    #[test]
+    fn separates_urc_from_response() {
+        let (mut receiver, urcs) = receiver();
+        receiver.start("AT+CWMODE?\r\n", false);
+        let frame = receiver
+            .receive(b"AT+CWMODE?\r\nWIFI CONNECTED\r\n+CWMODE:1\r\n0,CONNECT\r\n\r\nOK\r\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame.outcome, Outcome::Ok);
+        assert_eq!(body(&mut receiver, frame), b"AT+CWMODE?\r\n+CWMODE:1\r\n");
+        assert_eq!(
+            *urcs.lock().unwrap(),
+            [Urc::WifiConnected, Urc::Connected(Some(0))]
+        );
+    }
+
+    #[test]
+    fn separates_ipd_with_newlines_from_response() {
+        let (mut receiver, urcs) = receiver();
+        receiver.start("AT\r\n", false);
+        assert_eq!(receiver.receive(b"AT\r\n+IPD,0,9:OK\r\n").unwrap(), None);
+        let frame = receiver.receive(b"ERROR\r\nOK\r\n").unwrap().unwrap();
+        assert_eq!(frame.outcome, Outcome::Ok);
+        assert_eq!(body(&mut receiver, frame), b"AT\r\n");
+        assert_eq!(
+            *urcs.lock().unwrap(),
+            [Urc::ReceivedData {
+                link_id: Some(0),
+                remote: None,
+                data: b"OK\r\nERROR".to_vec(),
+            }]
+        );
+    }
+
+    #[test]
+    fn skips_corrupted_ipd_length() {
+        let (mut receiver, urcs) = receiver();
+        receiver.start("AT+CWMODE?\r\n", false);
+        let frame = receiver
+            .receive(b"+IPD,99999:ab\r\n+CWMODE:2\r\n\r\nOK\r\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame.outcome, Outcome::Ok);
+        assert!(urcs.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn clear_dispatches_urcs_and_drops_stale_responses() {
+        let (mut receiver, urcs) = receiver();
+        receiver.start("AT\r\n", false);
+        assert_eq!(
+            receiver
+                .receive(b"\r\nOK\r\nWIFI GOT IP\r\nstale line\r\nWIFI DISCONNE")
+                .unwrap(),
+            Some(Frame {
+                body_len: 0,
+                len: 6,
+                outcome: Outcome::Ok
+            })
+        );
+        receiver.start("AT\r\n", false);
+        assert_eq!(*urcs.lock().unwrap(), [Urc::WifiGotIp]);
+        // The incomplete line is kept, as it could be the start of a URC
+        assert_eq!(receiver.pending(), b"WIFI DISCONNE");
+
+        let frame = receiver
+            .receive(b"CT\r\nAT\r\n\r\nOK\r\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(body(&mut receiver, frame), b"AT\r\n");
+        assert_eq!(*urcs.lock().unwrap(), [Urc::WifiGotIp, Urc::WifiDisconnect]);
+    }
+    #[test]
+    fn drops_late_response_to_command_that_timed_out() {
+        let (mut receiver, _) = receiver();
+        receiver.start("AT\r\n", false);
+        assert!(matches!(receiver.time_out(&Test), Err(Error::Timeout)));
+
+        receiver.start("AT+CWMODE?\r\n", false);
+        let frame = receiver
+            .receive(b"AT\r\n\r\nOK\r\nAT+CWMODE?\r\n+CWMODE:1\r\n\r\nOK\r\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(body(&mut receiver, frame), b"AT+CWMODE?\r\n+CWMODE:1\r\n");
+    }
+
+    #[test]
+    fn drops_late_response_without_echo() {
+        let (mut receiver, _) = receiver();
+        receiver.start("AT\r\n", false);
+        assert!(matches!(receiver.time_out(&Test), Err(Error::Timeout)));
+
+        receiver.start("AT+CWMODE?\r\n", false);
+        assert_eq!(receiver.receive(b"\r\nOK\r\n").unwrap(), None);
+        let frame = receiver
+            .receive(b"+CWMODE:1\r\n\r\nOK\r\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(body(&mut receiver, frame), b"+CWMODE:1\r\n");
+    }
+}
@@ -0,0 +1,112 @@
+use crate::Error;
+use core::time::Duration;
+
+/// When and how often an interface retries a command that failed.
+///
+/// The delay before a retry starts at `initial_backoff`, and doubles after every attempt up to `max_backoff`. By default commands are not retried.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// The amount of times a command is retried. `0` disables retrying.
+    pub max_retries: u32,
+    /// Retry when the module responds with `busy p...` or `busy s...`.
+    pub retry_on_busy: bool,
+    /// Retry when no response is received in time.
+    pub retry_on_timeout: bool,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Never retry.
+    pub const NONE: RetryPolicy = RetryPolicy {
+        max_retries: 0,
+        retry_on_busy: false,
+        retry_on_timeout: false,
+        initial_backoff: Duration::from_millis(0),
+        max_backoff: Duration::from_millis(0),
+    };
+
+    /// Retry up to `max_retries` times when the module is busy or does not respond, starting with a backoff of 100 ms up to 5 seconds.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            retry_on_busy: true,
+            retry_on_timeout: true,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+
+    /// Returns `true` if a command that failed with `error` should be retried, given the amount of retries that were already done.
+    pub fn should_retry(&self, error: &Error, retries: u32) -> bool {
+        if retries >= self.max_retries {
+            return false;
+        }
+        match error {
+            Error::Busy(_) => self.retry_on_busy,
+            Error::Timeout => self.retry_on_timeout,
+            _ => false,
+        }
+    }
+
+    /// How long to wait before the next retry, given the amount of retries that were already done.
+    pub fn backoff(&self, retries: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .checked_mul(1 << retries.min(31))
+            .unwrap_or(self.max_backoff);
+        backoff.min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Busy, Rejection};
+
+    #[test]
+    fn doubles_backoff_up_to_max() {
+        let policy = RetryPolicy::new(10);
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(5), Duration::from_millis(3200));
+        assert_eq!(policy.backoff(6), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn caps_backoff_at_large_retry_counts() {
+        let policy = RetryPolicy::new(u32::MAX);
+        for retries in [31, 32, 64, 1000, u32::MAX].iter() {
+            assert_eq!(policy.backoff(*retries), Duration::from_secs(5));
+        }
+        let policy = RetryPolicy {
+            initial_backoff: Duration::MAX,
+            max_backoff: Duration::MAX,
+            ..policy
+        };
+        assert_eq!(policy.backoff(u32::MAX), Duration::MAX);
+        assert_eq!(RetryPolicy::NONE.backoff(u32::MAX), Duration::ZERO);
+    }
+
+    #[test]
+    fn retries_busy_and_timeout_up_to_max_retries() {
+        let policy = RetryPolicy::new(2);
+        assert!(policy.should_retry(&Error::Busy(Busy::Processing), 0));
+        assert!(policy.should_retry(&Error::Timeout, 1));
+        assert!(!policy.should_retry(&Error::Timeout, 2));
+        assert!(!policy.should_retry(&Error::Rejected(Rejection::Fail), 0));
+
+        let policy = RetryPolicy {
+            retry_on_busy: false,
+            ..policy
+        };
+        assert!(!policy.should_retry(&Error::Busy(Busy::Sending), 0));
+        assert!(!RetryPolicy::NONE.should_retry(&Error::Timeout, 0));
+    }
+}
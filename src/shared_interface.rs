@@ -0,0 +1,386 @@
+use crate::framer::Frame;
+use crate::receiver::{self, Receiver};
+use crate::{encode_command, Command, Error, Interface, RetryPolicy, Transport, Urc};
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError, Weak};
+use std::time::Duration;
+
+/// How long the reader thread blocks on a read before checking if the interface was dropped.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A handle to an interface that can be cloned and shared between threads.
+///
+/// A background thread reads from the module, matches responses to the command that is being sent, and passes [Urc]s to the URC handler as soon as they are received. Concurrent calls to [send](SharedInterface::send) are queued and sent one at a time.
+///
+/// A response that arrives after its command timed out is dropped, so it is not mistaken for the response to the next command.
+///
+/// The thread stops when the last handle is dropped.
+#[derive(Clone)]
+pub struct SharedInterface {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    /// Held for the duration of a command, so commands are sent one at a time.
+    writer: Mutex<Box<dyn Write + Send>>,
+    state: Mutex<State>,
+    response_received: Condvar,
+}
+
+struct State {
+    receiver: Receiver<Vec<u8>>,
+    timeout: Duration,
+    retry_policy: RetryPolicy,
+    response: Option<(Vec<u8>, Frame)>,
+    /// Set when the reader thread stopped because of an error.
+    closed: Option<io::ErrorKind>,
+}
+
+impl<T: Transport + Send + 'static> Interface<T> {
+    /// Turn this interface into a [SharedInterface].
+    ///
+    /// This needs a second handle to the transport for the reader thread, see [Transport::try_clone]. The URC handler is kept.
+    pub fn into_shared(self) -> Result<SharedInterface, Error> {
+        let mut reader = self.port.try_clone()?;
+        reader.set_timeout(POLL_INTERVAL)?;
+        Ok(SharedInterface::spawn(reader, self.port, self.receiver))
+    }
+}
+
+impl SharedInterface {
+    /// Create a shared interface from separate reading and writing halves of a stream.
+    ///
+    /// Reads on `reader` should time out regularly, so the reader thread can stop when the interface is dropped.
+    pub fn new(reader: impl Read + Send + 'static, writer: impl Write + Send + 'static) -> Self {
+        Self::spawn(reader, writer, Receiver::new(Vec::new()))
+    }
+
+    fn spawn(
+        reader: impl Read + Send + 'static,
+        writer: impl Write + Send + 'static,
+        receiver: Receiver<Vec<u8>>,
+    ) -> Self {
+        let shared = Arc::new(Shared {
+            writer: Mutex::new(Box::new(writer)),
+            state: Mutex::new(State {
+                receiver,
+                timeout: Duration::from_secs(30),
+                retry_policy: RetryPolicy::NONE,
+                response: None,
+                closed: None,
+            }),
+            response_received: Condvar::new(),
+        });
+        let weak = Arc::downgrade(&shared);
+        std::thread::spawn(move || read_loop(weak, reader));
+        Self { shared }
+    }
+
+    /// Set how long a command may take if it does not have its own [timeout](Command::timeout). Defaults to 30 seconds.
+    pub fn set_timeout(&self, timeout: Duration) {
+        self.shared.state().timeout = timeout;
+    }
+
+    /// Set when failed commands are retried.
+    pub fn set_retry_policy(&self, retry_policy: RetryPolicy) {
+        self.shared.state().retry_policy = retry_policy;
+    }
+
+    /// Set the callback that receives the [Urc]s sent by the module.
+    ///
+    /// The handler is called on the reader thread. It must not send commands, as no other data can be received until it returns.
+    pub fn set_urc_handler(&self, handler: impl FnMut(Urc) + Send + 'static) {
+        self.shared
+            .state()
+            .receiver
+            .set_urc_handler(Box::new(handler));
+    }
+
+    pub fn send<C: Command>(&self, command: C) -> Result<C::Output, Error> {
+        let retry_policy = self.shared.state().retry_policy;
+        let mut retries = 0;
+        loop {
+            match self.send_once(&command) {
+                Err(e) if retry_policy.should_retry(&e, retries) => {
+                    std::thread::sleep(retry_policy.backoff(retries));
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn send_once<C: Command>(&self, command: &C) -> Result<C::Output, Error> {
+        let mut writer = self
+            .shared
+            .writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let mut line = String::new();
+        encode_command(command, &mut line)?;
+
+        let timeout = {
+            let mut state = self.shared.state();
+            state.receiver.start(&line, command.expects_prompt());
+            state.response = None;
+            command.timeout().unwrap_or(state.timeout)
+        };
+
+        let result = writer
+            .write_all(line.as_bytes())
+            .and_then(|()| writer.flush());
+        let mut state = self.shared.state();
+        if let Err(e) = result {
+            state.receiver.cancel();
+            return Err(e.into());
+        }
+
+        let (mut state, _) = self
+            .shared
+            .response_received
+            .wait_timeout_while(state, timeout, |state| {
+                state.response.is_none() && state.closed.is_none()
+            })
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let (bytes, frame) = match (state.response.take(), state.closed) {
+            (Some(response), _) => response,
+            (None, Some(kind)) => {
+                state.receiver.cancel();
+                return Err(io::Error::new(kind, "The reader thread has stopped").into());
+            }
+            (None, None) => return state.receiver.time_out(command),
+        };
+        drop(state);
+        receiver::decode(command, &bytes, frame)
+    }
+}
+
+impl Shared {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn read_loop(shared: Weak<Shared>, mut reader: impl Read) {
+    let mut buff = [0u8; 1024];
+    loop {
+        let result = reader.read(&mut buff);
+        let shared = match shared.upgrade() {
+            Some(shared) => shared,
+            None => return,
+        };
+        let mut state = shared.state();
+
+        let n = match result {
+            Ok(0) => {
+                state.closed = Some(io::ErrorKind::UnexpectedEof);
+                shared.response_received.notify_all();
+                return;
+            }
+            Ok(n) => n,
+            Err(e) if is_transient(&e) => continue,
+            Err(e) => {
+                state.closed = Some(e.kind());
+                shared.response_received.notify_all();
+                return;
+            }
+        };
+
+        if state.receiver.extend(&buff[..n]).is_err() {
+            continue;
+        }
+        // Responses that do not fit can only fail with a fixed size buffer
+        while let Ok(Some(frame)) = state.receiver.next_response() {
+            let bytes = state.receiver.take(frame);
+            state.response = Some((bytes, frame));
+            shared.response_received.notify_all();
+        }
+    }
+}
+
+fn is_transient(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{GetWifiMode, Test, WifiMode};
+    use std::sync::mpsc::{self, RecvTimeoutError};
+
+    /// The reading end of an in-memory pipe. Reads time out like a serial port, and return 0 when the writing end is dropped.
+    struct PipeReader {
+        chunks: mpsc::Receiver<Vec<u8>>,
+        chunk: Vec<u8>,
+    }
+
+    impl Read for PipeReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.chunk.is_empty() {
+                self.chunk = match self.chunks.recv_timeout(POLL_INTERVAL) {
+                    Ok(chunk) => chunk,
+                    Err(RecvTimeoutError::Timeout) => return Err(io::ErrorKind::TimedOut.into()),
+                    Err(RecvTimeoutError::Disconnected) => return Ok(0),
+                };
+            }
+            let len = buf.len().min(self.chunk.len());
+            buf[..len].copy_from_slice(&self.chunk[..len]);
+            self.chunk.drain(..len);
+            Ok(len)
+        }
+    }
+
+    /// The writing end of an in-memory pipe, which sends every write as one chunk.
+    struct PipeWriter(mpsc::Sender<Vec<u8>>);
+
+    impl Write for PipeWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0
+                .send(buf.to_vec())
+                .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn pipe() -> (PipeWriter, PipeReader) {
+        let (sender, chunks) = mpsc::channel();
+        (
+            PipeWriter(sender),
+            PipeReader {
+                chunks,
+                chunk: Vec::new(),
+            },
+        )
+    }
+
+    /// A shared interface, and the ends of the pipes the module reads commands from and writes responses to.
+    fn connect() -> (SharedInterface, PipeReader, PipeWriter) {
+        let (command_writer, commands) = pipe();
+        let (module, response_reader) = pipe();
+        let interface = SharedInterface::new(response_reader, command_writer);
+        (interface, commands, module)
+    }
+
+    fn read_command(commands: &mut PipeReader) -> Vec<u8> {
+        let mut buf = [0; 64];
+        loop {
+            match commands.read(&mut buf) {
+                Ok(len) => return buf[..len].to_vec(),
+                Err(e) if is_transient(&e) => continue,
+                Err(e) => panic!("{}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let (interface, mut commands, mut module) = connect();
+        let urcs = Arc::new(Mutex::new(Vec::new()));
+        let handler_urcs = urcs.clone();
+        interface.set_urc_handler(move |urc| handler_urcs.lock().unwrap().push(urc));
+
+        let module = std::thread::spawn(move || {
+            assert_eq!(read_command(&mut commands), b"AT+CWMODE?\r\n");
+            module
+                .write_all(b"AT+CWMODE?\r\nWIFI GOT IP\r\n+CWMODE:2\r\n\r\nOK\r\n")
+                .unwrap();
+        });
+        assert_eq!(interface.send(GetWifiMode).unwrap(), WifiMode::ApMode);
+        module.join().unwrap();
+        assert_eq!(*urcs.lock().unwrap(), [Urc::WifiGotIp]);
+    }
+
+    #[test]
+    fn drops_late_response_to_command_that_timed_out() {
+        let (interface, mut commands, mut module) = connect();
+        interface.set_timeout(Duration::from_millis(300));
+
+        let module = std::thread::spawn(move || {
+            // Answer the first command only together with the second
+            read_command(&mut commands);
+            read_command(&mut commands);
+            module
+                .write_all(
+                    b"AT+CWMODE?\r\n+CWMODE:1\r\n\r\nOK\r\n\
+                      AT+CWMODE?\r\n+CWMODE:2\r\n\r\nOK\r\n",
+                )
+                .unwrap();
+            read_command(&mut commands);
+            module
+                .write_all(b"AT+CWMODE?\r\n+CWMODE:3\r\n\r\nOK\r\n")
+                .unwrap();
+        });
+        assert!(matches!(interface.send(GetWifiMode), Err(Error::Timeout)));
+        assert_eq!(interface.send(GetWifiMode).unwrap(), WifiMode::ApMode);
+        assert_eq!(
+            interface.send(GetWifiMode).unwrap(),
+            WifiMode::ApStationMode
+        );
+        module.join().unwrap();
+    }
+
+    #[test]
+    fn drops_late_response_without_echo() {
+        let (interface, mut commands, mut module) = connect();
+        interface.set_timeout(Duration::from_millis(300));
+
+        let module = std::thread::spawn(move || {
+            read_command(&mut commands);
+            read_command(&mut commands);
+            module
+                .write_all(b"+CWMODE:1\r\n\r\nOK\r\n+CWMODE:2\r\n\r\nOK\r\n")
+                .unwrap();
+        });
+        assert!(matches!(interface.send(GetWifiMode), Err(Error::Timeout)));
+        assert_eq!(interface.send(GetWifiMode).unwrap(), WifiMode::ApMode);
+        module.join().unwrap();
+    }
+
+    #[test]
+    fn retries_command_that_was_lost() {
+        let (interface, mut commands, mut module) = connect();
+        interface.set_timeout(Duration::from_millis(300));
+        interface.set_retry_policy(RetryPolicy::new(3));
+
+        let module = std::thread::spawn(move || {
+            // The first attempt is lost, the retry is answered
+            read_command(&mut commands);
+            assert_eq!(read_command(&mut commands), b"AT+CWMODE?\r\n");
+            module
+                .write_all(b"AT+CWMODE?\r\n+CWMODE:1\r\n\r\nOK\r\n")
+                .unwrap();
+            (commands, module)
+        });
+        assert_eq!(interface.send(GetWifiMode).unwrap(), WifiMode::StationMode);
+        let (mut commands, _module) = module.join().unwrap();
+        // The answer to the retry was used, instead of retrying again
+        drop(interface);
+        assert_eq!(read_command(&mut commands), b"");
+    }
+
+    #[test]
+    fn forgets_commands_that_timed_out_when_module_restarts() {
+        let (interface, mut commands, mut module) = connect();
+        interface.set_timeout(Duration::from_millis(300));
+
+        let module = std::thread::spawn(move || {
+            read_command(&mut commands);
+            read_command(&mut commands);
+            // Without the restart, the response without echo would be taken as the late response to the first command
+            module.write_all(b"\r\nready\r\n\r\nOK\r\n").unwrap();
+            module
+        });
+        assert!(matches!(interface.send(GetWifiMode), Err(Error::Timeout)));
+        assert!(!interface.send(Test).unwrap());
+        module.join().unwrap();
+    }
+}
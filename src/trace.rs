@@ -0,0 +1,189 @@
+//! Logging of the traffic to and from the module, through the [log] crate.
+//!
+//! Commands are logged on the `at_protocol::tx` target and responses on the `at_protocol::rx` target, both at debug level.
+//! Secrets are redacted in both directions: in the command, in its echo, and in the lines of its response.
+
+use crate::Command;
+use core::fmt::{self, Write};
+
+pub(crate) const TX_TARGET: &str = "at_protocol::tx";
+pub(crate) const RX_TARGET: &str = "at_protocol::rx";
+
+/// Log a command that is about to be sent. Secrets are redacted with [Command::encode_redacted].
+pub(crate) fn transmit<C: Command>(command: &C) {
+    if log::log_enabled!(target: TX_TARGET, log::Level::Debug) {
+        log::debug!(target: TX_TARGET, "> {}", Redacted(command));
+    }
+}
+
+/// Log the response to `command`. The echo of the command is redacted with [Command::encode_redacted], and the other lines with [Command::redact_response_line].
+pub(crate) fn response<C: Command>(command: &C, bytes: &[u8]) {
+    if log::log_enabled!(target: RX_TARGET, log::Level::Debug) {
+        log::debug!(target: RX_TARGET, "< {}", RedactedResponse(command, bytes));
+    }
+}
+
+/// Log an unsolicited result code that was received.
+pub(crate) fn urc(bytes: &[u8]) {
+    log::debug!(target: RX_TARGET, "< {}", Dump(bytes));
+}
+
+/// Log that received bytes were dropped, such as a late response to a command that timed out. Only the length is logged, as it is not known which command they belong to, so secrets in them cannot be redacted.
+pub(crate) fn discard(bytes: &[u8]) {
+    if !bytes.is_empty() {
+        log::debug!(target: RX_TARGET, "< dropped {} bytes", bytes.len());
+    }
+}
+
+/// Formats a command with [Command::encode_redacted] as a quoted string, without allocating a buffer for it.
+struct Redacted<'a, C>(&'a C);
+
+impl<C: Command> fmt::Display for Redacted<'_, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_char('"')?;
+        // A command that cannot be encoded is not sent, so it does not matter what is logged for it
+        let _ = self.0.encode_redacted(&mut Escape(f));
+        f.write_char('"')
+    }
+}
+
+/// Formats a response as a quoted string with its secrets redacted, or as a hex dump if it is not valid UTF-8.
+struct RedactedResponse<'a, C>(&'a C, &'a [u8]);
+
+impl<C: Command> fmt::Display for RedactedResponse<'_, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let response = match core::str::from_utf8(self.1) {
+            Ok(response) => response.trim(),
+            Err(_) => return Dump(self.1).fmt(f),
+        };
+        f.write_char('"')?;
+        for (index, line) in response.split_inclusive('\n').enumerate() {
+            let content = line.trim_end_matches(['\r', '\n']);
+            // A line that fails to redact is cut short, so nothing is written after the secret that failed
+            let _ = if index == 0 && is_echo(self.0, content) {
+                self.0.encode_redacted(&mut Escape(f))
+            } else {
+                self.0.redact_response_line(content, &mut Escape(f))
+            };
+            write!(f, "{}", line[content.len()..].escape_debug())?;
+        }
+        f.write_char('"')
+    }
+}
+
+/// Whether `line` is the echo of the command, without allocating a buffer to encode it in.
+fn is_echo<C: Command>(command: &C, line: &str) -> bool {
+    let mut rest = Some(line);
+    command.encode(&mut Compare(&mut rest)).is_ok() && rest == Some("")
+}
+
+/// Compares what is written with the start of a line, ignoring newlines. The rest of the line is set to `None` once they differ.
+struct Compare<'a, 'b>(&'a mut Option<&'b str>);
+
+impl Write for Compare<'_, '_> {
+    fn write_str(&mut self, str: &str) -> fmt::Result {
+        for c in str.chars().filter(|c| !matches!(c, '\r' | '\n')) {
+            *self.0 = self.0.and_then(|rest| rest.strip_prefix(c));
+        }
+        Ok(())
+    }
+}
+
+/// Escapes special characters like `{:?}`, and leaves out newlines.
+struct Escape<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+impl Write for Escape<'_, '_> {
+    fn write_str(&mut self, str: &str) -> fmt::Result {
+        for c in str.chars().filter(|c| !matches!(c, '\r' | '\n')) {
+            write!(self.0, "{}", c.escape_debug())?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats bytes as a quoted string if they are valid UTF-8, or as a hex dump otherwise.
+struct Dump<'a>(&'a [u8]);
+
+impl fmt::Display for Dump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match core::str::from_utf8(self.0) {
+            Ok(str) => write!(f, "{:?}", str.trim()),
+            Err(_) => {
+                f.write_str("[")?;
+                for (index, byte) in self.0.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(" ")?;
+                    }
+                    write!(f, "{:02x}", byte)?;
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::command::{ConnectToAp, GetSoftAp};
+    use std::string::{String, ToString};
+    use std::sync::Mutex;
+    use std::vec::Vec;
+
+    /// A logger that keeps the messages on the traffic targets.
+    struct Capture(Mutex<Vec<String>>);
+
+    impl log::Log for Capture {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.target() == TX_TARGET || metadata.target() == RX_TARGET
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+    /// The messages that were logged. Other tests log at the same time, so this can contain more than the calling test logged.
+    fn logged() -> Vec<String> {
+        CAPTURE.0.lock().unwrap().clone()
+    }
+
+    fn install() {
+        // Only the first test sets the logger, which is shared by all tests
+        let _ = log::set_logger(&CAPTURE);
+        log::set_max_level(log::LevelFilter::Debug);
+    }
+
+    #[test]
+    fn redacts_password_in_command_and_echo() {
+        install();
+        let command = ConnectToAp::new("home", "hunter22");
+        transmit(&command);
+        response(&command, b"AT+CWJAP=\"home\",\"hunter22\"\r\n\r\nOK\r\n");
+
+        let logged = logged();
+        assert!(logged.contains(&r#"> "AT+CWJAP=\"home\",\"***\"""#.to_string()));
+        assert!(logged.contains(&r#"< "AT+CWJAP=\"home\",\"***\"\r\n\r\nOK""#.to_string()));
+        assert!(!logged.iter().any(|message| message.contains("hunter22")));
+    }
+
+    #[test]
+    fn redacts_password_in_response_line() {
+        install();
+        response(
+            &GetSoftAp,
+            b"AT+CWSAP?\r\n+CWSAP:\"ap\",\"swordfish\",5,3,4,0\r\n\r\nOK\r\n",
+        );
+
+        let logged = logged();
+        assert!(logged
+            .contains(&r#"< "AT+CWSAP?\r\n+CWSAP:\"ap\",\"***\",5,3,4,0\r\n\r\nOK""#.to_string()));
+        assert!(!logged.iter().any(|message| message.contains("swordfish")));
+    }
+}
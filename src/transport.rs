@@ -0,0 +1,46 @@
+use crate::Error;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+#[cfg(feature = "serial")]
+use serialport::SerialPort;
+
+/// A byte stream that an [Interface](crate::Interface) can send AT commands over.
+///
+/// This is implemented for serial ports and TCP streams (e.g. a ser2net bridge). Anything else that is `Read + Write` can implement this to be used with [Interface::with_transport](crate::Interface::with_transport).
+pub trait Transport: Read + Write {
+    /// Set how long a single read is allowed to block before failing.
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), Error>;
+
+    /// Create a second handle to the same stream, which is used to read on a background thread by [SharedInterface](crate::SharedInterface).
+    ///
+    /// Not every transport can be cloned, so by default this fails with `ErrorKind::Unsupported`.
+    fn try_clone(&self) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        Err(std::io::Error::from(std::io::ErrorKind::Unsupported).into())
+    }
+}
+
+#[cfg(feature = "serial")]
+impl Transport for Box<dyn SerialPort> {
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), Error> {
+        SerialPort::set_timeout(self.as_mut(), timeout).map_err(Into::into)
+    }
+
+    fn try_clone(&self) -> Result<Self, Error> {
+        SerialPort::try_clone(self.as_ref()).map_err(Into::into)
+    }
+}
+
+impl Transport for TcpStream {
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), Error> {
+        self.set_read_timeout(Some(timeout)).map_err(Into::into)
+    }
+
+    fn try_clone(&self) -> Result<Self, Error> {
+        TcpStream::try_clone(self).map_err(Into::into)
+    }
+}
@@ -0,0 +1,208 @@
+//! Unsolicited result codes, which the module sends at any time.
+
+use alloc::vec::Vec;
+use core::net::SocketAddr;
+
+/// An unsolicited result code (URC). These are sent by the module at any time, and are separated from the responses to commands.
+///
+/// Use the `set_urc_handler` method of the interfaces to receive them.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub enum Urc {
+    /// `ready`: the module has (re)started.
+    Ready,
+    /// `WIFI CONNECTED`
+    WifiConnected,
+    /// `WIFI GOT IP`
+    WifiGotIp,
+    /// `WIFI DISCONNECT`
+    WifiDisconnect,
+    /// `[<link ID>,]CONNECT`: a connection was opened. The link ID is only sent in multiple connection mode.
+    Connected(Option<u8>),
+    /// `[<link ID>,]CLOSED`: a connection was closed.
+    Closed(Option<u8>),
+    /// `[<link ID>,]CONNECT FAIL`: a connection could not be opened.
+    ConnectFailed(Option<u8>),
+    /// `+IPD,[<link ID>,]<len>[,<remote IP>,<remote port>]:<data>`: data was received on a connection.
+    ReceivedData {
+        link_id: Option<u8>,
+        /// Only sent when enabled with `AT+CIPDINFO=1`.
+        remote: Option<SocketAddr>,
+        data: Vec<u8>,
+    },
+}
+
+/// The largest data length accepted in a `+IPD`. The firmware passes on at most a few packets at a time, so a longer length is a corrupted header.
+///
+/// Without this limit a corrupted length would make the framer wait for data that never arrives, swallowing every following response.
+const MAX_IPD_LEN: usize = 8192;
+
+/// The result of trying to parse a `+IPD` URC.
+pub(crate) enum Ipd {
+    /// The header or data has not been fully received yet.
+    Incomplete,
+    /// The URC and the amount of bytes it takes up.
+    Complete(Urc, usize),
+    Invalid,
+}
+
+impl Urc {
+    /// Parse a single line, without the trailing newline.
+    pub(crate) fn parse(line: &[u8]) -> Option<Urc> {
+        Some(match line {
+            b"ready" => Urc::Ready,
+            b"WIFI CONNECTED" => Urc::WifiConnected,
+            b"WIFI GOT IP" => Urc::WifiGotIp,
+            b"WIFI DISCONNECT" => Urc::WifiDisconnect,
+            _ => {
+                let (link_id, event) = match line.iter().position(|b| *b == b',') {
+                    Some(comma) => (Some(parse_number(&line[..comma])?), &line[comma + 1..]),
+                    None => (None, line),
+                };
+                match event {
+                    b"CONNECT" => Urc::Connected(link_id),
+                    b"CLOSED" => Urc::Closed(link_id),
+                    b"CONNECT FAIL" => Urc::ConnectFailed(link_id),
+                    _ => return None,
+                }
+            }
+        })
+    }
+
+    /// Parse a `+IPD` URC at the start of `input`. The data may contain newlines, so this has to be done before splitting lines.
+    pub(crate) fn parse_ipd(input: &[u8]) -> Ipd {
+        // An IPv6 remote address contains colons, but is quoted
+        let mut quoted = false;
+        let header_end = match input.iter().position(|b| {
+            quoted ^= *b == b'"';
+            *b == b':' && !quoted
+        }) {
+            Some(colon) => colon,
+            None if input.contains(&b'\n') => return Ipd::Invalid,
+            None => return Ipd::Incomplete,
+        };
+        let header = match core::str::from_utf8(&input[b"+IPD,".len()..header_end]) {
+            Ok(header) => header,
+            Err(_) => return Ipd::Invalid,
+        };
+        let fields: Vec<&str> = header.split(',').collect();
+        let (link_id, len, remote) = match fields.as_slice() {
+            [len] => (None, *len, None),
+            [link_id, len] => (Some(*link_id), *len, None),
+            [len, ip, port] => (None, *len, Some((*ip, *port))),
+            [link_id, len, ip, port] => (Some(*link_id), *len, Some((*ip, *port))),
+            _ => return Ipd::Invalid,
+        };
+
+        let link_id = match link_id.map(str::parse).transpose() {
+            Ok(link_id) => link_id,
+            Err(_) => return Ipd::Invalid,
+        };
+        let len: usize = match len.parse() {
+            Ok(len) if len <= MAX_IPD_LEN => len,
+            _ => return Ipd::Invalid,
+        };
+        let remote = match remote {
+            Some((ip, port)) => match (ip.trim_matches('"').parse(), port.parse()) {
+                (Ok(ip), Ok(port)) => Some(SocketAddr::new(ip, port)),
+                _ => return Ipd::Invalid,
+            },
+            None => None,
+        };
+
+        let data_start = header_end + 1;
+        let data_end = data_start + len;
+        match input.get(data_start..data_end) {
+            Some(data) => Ipd::Complete(
+                Urc::ReceivedData {
+                    link_id,
+                    remote,
+                    data: data.to_vec(),
+                },
+                data_end,
+            ),
+            None => Ipd::Incomplete,
+        }
+    }
+}
+
+fn parse_number(digits: &[u8]) -> Option<u8> {
+    core::str::from_utf8(digits).ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    fn received(link_id: Option<u8>, remote: Option<SocketAddr>, data: &[u8]) -> Urc {
+        Urc::ReceivedData {
+            link_id,
+            remote,
+            data: data.to_vec(),
+        }
+    }
+
+    fn complete(input: &[u8]) -> Option<(Urc, usize)> {
+        match Urc::parse_ipd(input) {
+            Ipd::Complete(urc, len) => Some((urc, len)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn parses_lines() {
+        assert_eq!(Urc::parse(b"ready"), Some(Urc::Ready));
+        assert_eq!(Urc::parse(b"WIFI GOT IP"), Some(Urc::WifiGotIp));
+        assert_eq!(Urc::parse(b"CONNECT"), Some(Urc::Connected(None)));
+        assert_eq!(Urc::parse(b"0,CLOSED"), Some(Urc::Closed(Some(0))));
+        assert_eq!(
+            Urc::parse(b"4,CONNECT FAIL"),
+            Some(Urc::ConnectFailed(Some(4)))
+        );
+        assert_eq!(Urc::parse(b"x,CLOSED"), None);
+        assert_eq!(Urc::parse(b"+CWMODE:1"), None);
+    }
+
+    #[test]
+    fn parses_ipd_with_newlines_in_data() {
+        assert_eq!(
+            complete(b"+IPD,0,7:a\r\nOK\r\nrest"),
+            Some((received(Some(0), None, b"a\r\nOK\r\n"), 16))
+        );
+        assert_eq!(
+            complete(b"+IPD,3:abc"),
+            Some((received(None, None, b"abc"), 10))
+        );
+    }
+
+    #[test]
+    fn parses_ipd_remote() {
+        let remote = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 8080);
+        assert_eq!(
+            complete(b"+IPD,1,2,\"1.2.3.4\",8080:xy").map(|(urc, _)| urc),
+            Some(received(Some(1), Some(remote), b"xy"))
+        );
+        let remote = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 80);
+        assert_eq!(
+            complete(b"+IPD,2,\"::1\",80:xy").map(|(urc, _)| urc),
+            Some(received(None, Some(remote), b"xy"))
+        );
+    }
+
+    #[test]
+    fn waits_for_ipd_data() {
+        assert!(matches!(Urc::parse_ipd(b"+IPD,0,5:ab"), Ipd::Incomplete));
+        assert!(matches!(Urc::parse_ipd(b"+IPD,0,5"), Ipd::Incomplete));
+    }
+
+    #[test]
+    fn rejects_invalid_ipd() {
+        assert!(matches!(Urc::parse_ipd(b"+IPD,x:ab"), Ipd::Invalid));
+        assert!(matches!(Urc::parse_ipd(b"+IPD,0\r\n"), Ipd::Invalid));
+        assert!(matches!(Urc::parse_ipd(b"+IPD,99999:ab"), Ipd::Invalid));
+        assert!(matches!(
+            Urc::parse_ipd(b"+IPD,99999999999999999999999:ab"),
+            Ipd::Invalid
+        ));
+    }
+}
@@ -0,0 +1,57 @@
+use at_protocol::command::{Argument, ListAp, MacAddress};
+use at_protocol::Command;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+fn argument(value: impl Argument) -> String {
+    let mut output = String::new();
+    value.write(&mut output).unwrap();
+    output
+}
+
+#[test]
+fn mac_address_round_trips() {
+    let mac: MacAddress = "0c:d6:BD:0e:50:10".parse().unwrap();
+    assert_eq!(mac, MacAddress([0x0c, 0xd6, 0xbd, 0x0e, 0x50, 0x10]));
+    assert_eq!(mac.to_string(), "0c:d6:bd:0e:50:10");
+    assert_eq!(mac.to_string().parse::<MacAddress>().unwrap(), mac);
+    assert_eq!(argument(mac), "\"0c:d6:bd:0e:50:10\"");
+}
+
+#[test]
+fn rejects_invalid_mac_addresses() {
+    for invalid in [
+        "",
+        "0c:d6:bd:0e:50",
+        "0c:d6:bd:0e:50:10:11",
+        "0c:d6:bd:0e:50:1",
+        "0c:d6:bd:0e:50:1g",
+        "0c-d6-bd-0e-50-10",
+    ] {
+        assert!(invalid.parse::<MacAddress>().is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn ip_addresses_are_quoted_arguments() {
+    assert_eq!(argument(Ipv4Addr::new(192, 168, 4, 1)), "\"192.168.4.1\"");
+    assert_eq!(argument(Ipv6Addr::LOCALHOST), "\"::1\"");
+    assert_eq!(
+        argument(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2))),
+        "\"10.0.0.2\""
+    );
+}
+
+#[test]
+fn list_ap_parses_mac_address() {
+    let access_points = ListAp
+        .decode(b"+CWLAP:(3,\"home\",-50,\"0c:d6:bd:0e:50:10\",6)\r\n")
+        .unwrap();
+    assert_eq!(
+        access_points[0].mac,
+        MacAddress([0x0c, 0xd6, 0xbd, 0x0e, 0x50, 0x10])
+    );
+
+    assert!(ListAp
+        .decode(b"+CWLAP:(3,\"home\",-50,\"not a mac\",6)\r\n")
+        .is_err());
+}
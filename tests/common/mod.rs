@@ -0,0 +1,19 @@
+//! Helpers shared by the integration tests.
+
+use at_protocol::Command;
+
+/// Encode a command, as it is sent to the module.
+#[allow(dead_code)]
+pub fn encode(command: &impl Command) -> String {
+    let mut output = String::new();
+    command.encode(&mut output).unwrap();
+    output
+}
+
+/// Encode a command with its secrets redacted, as it is logged.
+#[allow(dead_code)]
+pub fn encode_redacted(command: &impl Command) -> String {
+    let mut output = String::new();
+    command.encode_redacted(&mut output).unwrap();
+    output
+}
@@ -0,0 +1,121 @@
+use at_protocol::command::{Persistence, SetSysStore, SetWifiMode, WifiMode};
+use at_protocol::{AtCommand, AtResponse, Command};
+use std::time::Duration;
+
+mod common;
+
+use common::{encode, encode_redacted};
+
+#[derive(AtCommand)]
+#[at(execute = "+RST", timeout_ms = 2000)]
+struct Restart;
+
+#[derive(AtCommand)]
+#[at(query = "+CWSAP", response = "+CWSAP", output = SoftAp)]
+struct GetSoftAp;
+
+#[derive(AtCommand)]
+#[at(set = "+CWSAP")]
+struct SetSoftAp<'a> {
+    ssid: &'a str,
+    #[at(redact)]
+    password: &'a str,
+    channel: u8,
+    ecn: u8,
+    max_connections: Option<u8>,
+    hidden: Option<bool>,
+}
+
+#[derive(AtResponse, Debug, PartialEq)]
+struct SoftAp {
+    ssid: String,
+    #[at(redact)]
+    password: String,
+    channel: u8,
+    ecn: u8,
+    max_connections: Option<u8>,
+    hidden: Option<bool>,
+}
+
+#[test]
+fn encodes_each_form() {
+    assert_eq!(encode(&Restart), "AT+RST\r\n");
+    assert_eq!(Restart.timeout(), Some(Duration::from_secs(2)));
+    assert_eq!(encode(&GetSoftAp), "AT+CWSAP?\r\n");
+    assert_eq!(
+        encode(&SetWifiMode(WifiMode::ApStationMode, Persistence::Default)),
+        "AT+CWMODE=3\r\n"
+    );
+}
+
+#[test]
+fn adds_persistence_suffix() {
+    assert_eq!(
+        encode(&SetWifiMode(WifiMode::StationMode, Persistence::Current)),
+        "AT+CWMODE_CUR=1\r\n"
+    );
+    assert_eq!(
+        encode(&SetWifiMode(WifiMode::StationMode, Persistence::Saved)),
+        "AT+CWMODE_DEF=1\r\n"
+    );
+    assert_eq!(encode(&SetSysStore(false)), "AT+SYSSTORE=0\r\n");
+}
+
+#[test]
+fn skips_missing_arguments() {
+    let mut command = SetSoftAp {
+        ssid: "my,ap",
+        password: "secret",
+        channel: 5,
+        ecn: 3,
+        max_connections: None,
+        hidden: Some(true),
+    };
+    assert_eq!(
+        encode(&command),
+        "AT+CWSAP=\"my\\,ap\",\"secret\",5,3,,1\r\n"
+    );
+    assert_eq!(
+        encode_redacted(&command),
+        "AT+CWSAP=\"my\\,ap\",\"***\",5,3,,1\r\n"
+    );
+
+    command.hidden = None;
+    assert_eq!(encode(&command), "AT+CWSAP=\"my\\,ap\",\"secret\",5,3\r\n");
+}
+
+#[test]
+fn decodes_response_struct() {
+    let output = GetSoftAp
+        .decode(b"AT+CWSAP?\r\n+CWSAP:\"my\\,ap\",\"secret\",5,3,,1\r\n")
+        .unwrap();
+    assert_eq!(
+        output,
+        SoftAp {
+            ssid: "my,ap".to_string(),
+            password: "secret".to_string(),
+            channel: 5,
+            ecn: 3,
+            max_connections: None,
+            hidden: Some(true),
+        }
+    );
+
+    assert!(GetSoftAp.decode(b"+CWSAP:\"ap\",\"pw\",x,3").is_err());
+    assert!(GetSoftAp.decode(b"OK").is_err());
+}
+
+#[test]
+fn redacts_response_fields() {
+    let mut output = String::new();
+    GetSoftAp
+        .redact_response_line("+CWSAP:\"my\\,ap\",\"secret\",5,3,,1", &mut output)
+        .unwrap();
+    assert_eq!(output, "+CWSAP:\"my\\,ap\",\"***\",5,3,,1");
+
+    let mut output = String::new();
+    Restart
+        .redact_response_line("+CWSAP:\"ap\",\"secret\"", &mut output)
+        .unwrap();
+    assert_eq!(output, "+CWSAP:\"ap\",\"secret\"");
+}
@@ -0,0 +1,58 @@
+use at_protocol::command::form::{IsSupported, Query, Set, Test};
+use at_protocol::command::{Cwmode, WifiMode};
+use at_protocol::{Busy, Command, Error, Outcome, Response};
+
+mod common;
+
+use common::encode;
+
+#[test]
+fn encodes_each_form() {
+    assert_eq!(encode(&Query(Cwmode)), "AT+CWMODE?\r\n");
+    assert_eq!(encode(&Set(Cwmode, WifiMode::ApMode)), "AT+CWMODE=2\r\n");
+    assert_eq!(encode(&Test(Cwmode)), "AT+CWMODE=?\r\n");
+}
+
+#[test]
+fn decodes_query() {
+    let mode = Query(Cwmode)
+        .decode(b"AT+CWMODE?\r\n+CWMODE:3\r\n")
+        .unwrap();
+    assert_eq!(mode, WifiMode::ApStationMode);
+}
+
+#[test]
+fn decodes_test_ranges() {
+    let allowed = Test(Cwmode)
+        .decode(b"AT+CWMODE=?\r\n+CWMODE:(0-3)\r\n")
+        .unwrap();
+    assert_eq!(allowed.ranges(), &[0..=3]);
+    assert!(allowed.contains(WifiMode::ApStationMode as i64));
+    assert!(!allowed.contains(4));
+
+    let allowed = Test(Cwmode).decode(b"+CWMODE:(1,3-4)").unwrap();
+    assert_eq!(allowed.ranges(), &[1..=1, 3..=4]);
+    assert!(!allowed.contains(2));
+
+    assert!(Test(Cwmode).decode(b"+CWMODE:(a-b)").is_err());
+}
+
+#[test]
+fn checks_support_with_query_form() {
+    let command = IsSupported("+SYSSTORE");
+    assert_eq!(encode(&command), "AT+SYSSTORE?\r\n");
+
+    let response = |outcome| Response {
+        body: b"AT+SYSSTORE?\r\n",
+        outcome,
+    };
+    assert!(command.decode_response(&response(Outcome::Ok)).unwrap());
+    assert!(!command
+        .decode_response(&response(Outcome::Error(Some(0x0109_0000))))
+        .unwrap());
+    assert!(!command.decode_response(&response(Outcome::Fail)).unwrap());
+    assert!(matches!(
+        command.decode_response(&response(Outcome::BusyProcessing)),
+        Err(Error::Busy(Busy::Processing))
+    ));
+}
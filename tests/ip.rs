@@ -0,0 +1,202 @@
+use at_protocol::command::{
+    DhcpRange, DhcpState, FirmwareFamily, GetDhcp, GetIpConfig, GetLocalAddresses, GetMacAddress,
+    MacAddress, Persistence, SetDhcp, SetDhcpServer, SetIpConfig, SetMacAddress, WifiMode,
+};
+use at_protocol::{Command, Error};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+mod common;
+
+use common::encode;
+
+const MAC: MacAddress = MacAddress([0x0c, 0xd6, 0xbd, 0x0e, 0x50, 0x10]);
+
+#[test]
+fn encodes_dhcp_for_each_firmware() {
+    let command = SetDhcp {
+        interfaces: WifiMode::StationMode,
+        enable: true,
+        persistence: Persistence::Default,
+        firmware: FirmwareFamily::EspAtV2,
+    };
+    assert_eq!(encode(&command), "AT+CWDHCP=1,1\r\n");
+
+    let command = SetDhcp {
+        interfaces: WifiMode::ApMode,
+        enable: false,
+        persistence: Persistence::Current,
+        firmware: FirmwareFamily::NonOs,
+    };
+    assert_eq!(encode(&command), "AT+CWDHCP_CUR=0,0\r\n");
+}
+
+#[test]
+fn encodes_dhcp_without_suffix_for_old_non_os_firmware() {
+    let command = SetDhcp {
+        interfaces: WifiMode::ApMode,
+        enable: true,
+        persistence: Persistence::Default,
+        firmware: FirmwareFamily::NonOs,
+    };
+    assert_eq!(encode(&command), "AT+CWDHCP=0,1\r\n");
+
+    let command = SetDhcp {
+        firmware: FirmwareFamily::EspAtV1,
+        ..command
+    };
+    assert_eq!(encode(&command), "AT+CWDHCP=1,2\r\n");
+}
+
+#[test]
+fn decodes_dhcp_for_each_firmware() {
+    let state = DhcpState {
+        station: true,
+        ap: false,
+    };
+    assert_eq!(
+        GetDhcp {
+            persistence: Persistence::Default,
+            firmware: FirmwareFamily::EspAtV2,
+        }
+        .decode(b"AT+CWDHCP?\r\n+CWDHCP:1\r\n")
+        .unwrap(),
+        state
+    );
+    assert_eq!(
+        GetDhcp {
+            persistence: Persistence::Current,
+            firmware: FirmwareFamily::NonOs,
+        }
+        .decode(b"AT+CWDHCP_CUR?\r\n+CWDHCP_CUR:2\r\n")
+        .unwrap(),
+        state
+    );
+}
+
+#[test]
+fn decodes_dhcp_without_suffix_for_old_non_os_firmware() {
+    let command = GetDhcp {
+        persistence: Persistence::Default,
+        firmware: FirmwareFamily::NonOs,
+    };
+    assert_eq!(encode(&command), "AT+CWDHCP?\r\n");
+    assert_eq!(
+        command.decode(b"AT+CWDHCP?\r\n+CWDHCP:1\r\n").unwrap(),
+        DhcpState {
+            station: false,
+            ap: true,
+        }
+    );
+}
+
+#[test]
+fn encodes_dhcp_server_range() {
+    let command = SetDhcpServer {
+        range: Some(DhcpRange {
+            lease_time: 120,
+            start: Ipv4Addr::new(192, 168, 4, 10),
+            end: Ipv4Addr::new(192, 168, 4, 20),
+        }),
+        persistence: Persistence::Saved,
+    };
+    assert_eq!(
+        encode(&command),
+        "AT+CWDHCPS_DEF=1,120,\"192.168.4.10\",\"192.168.4.20\"\r\n"
+    );
+    let command = SetDhcpServer {
+        range: None,
+        persistence: Persistence::Default,
+    };
+    assert_eq!(encode(&command), "AT+CWDHCPS=0\r\n");
+}
+
+#[test]
+fn encodes_static_address_per_interface() {
+    let command = SetIpConfig {
+        interface: WifiMode::StationMode,
+        ip: Ipv4Addr::new(192, 168, 0, 2),
+        gateway: Ipv4Addr::new(192, 168, 0, 1),
+        netmask: Ipv4Addr::new(255, 255, 255, 0),
+        persistence: Persistence::Current,
+    };
+    assert_eq!(
+        encode(&command),
+        "AT+CIPSTA_CUR=\"192.168.0.2\",\"192.168.0.1\",\"255.255.255.0\"\r\n"
+    );
+
+    let command = SetIpConfig {
+        interface: WifiMode::ApStationMode,
+        ..command
+    };
+    assert!(matches!(
+        command.encode(&mut String::new()),
+        Err(Error::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn decodes_ip_config() {
+    let config = GetIpConfig {
+        interface: WifiMode::ApMode,
+        persistence: Persistence::Default,
+    }
+    .decode(
+        b"AT+CIPAP?\r\n+CIPAP:ip:\"192.168.4.1\"\r\n+CIPAP:gateway:\"192.168.4.1\"\r\n\
+          +CIPAP:netmask:\"255.255.255.0\"\r\n+CIPAP:ip6ll:\"fe80::1\"\r\n",
+    )
+    .unwrap();
+    assert_eq!(config.ip, Ipv4Addr::new(192, 168, 4, 1));
+    assert_eq!(config.netmask, Ipv4Addr::new(255, 255, 255, 0));
+    assert_eq!(
+        config.ip6_link_local,
+        Some(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1))
+    );
+    assert_eq!(config.ip6_global, None);
+
+    assert!(GetIpConfig {
+        interface: WifiMode::StationMode,
+        persistence: Persistence::Default,
+    }
+    .decode(b"+CIPAP:ip:\"192.168.4.1\"\r\n")
+    .is_err());
+}
+
+#[test]
+fn encodes_and_decodes_mac_address() {
+    let command = SetMacAddress {
+        interface: WifiMode::ApMode,
+        mac: MAC,
+        persistence: Persistence::Default,
+    };
+    assert_eq!(encode(&command), "AT+CIPAPMAC=\"0c:d6:bd:0e:50:10\"\r\n");
+
+    let command = GetMacAddress {
+        interface: WifiMode::StationMode,
+        persistence: Persistence::Current,
+    };
+    assert_eq!(encode(&command), "AT+CIPSTAMAC_CUR?\r\n");
+    assert_eq!(
+        command
+            .decode(b"+CIPSTAMAC_CUR:\"0c:d6:bd:0e:50:10\"\r\n")
+            .unwrap(),
+        MAC
+    );
+}
+
+#[test]
+fn decodes_local_addresses() {
+    let addresses = GetLocalAddresses
+        .decode(
+            b"AT+CIFSR\r\n+CIFSR:APIP,\"192.168.4.1\"\r\n+CIFSR:APMAC,\"0c:d6:bd:0e:50:10\"\r\n\
+              +CIFSR:STAIP,\"0.0.0.0\"\r\n+CIFSR:STAMAC,\"0c:d6:bd:0e:50:11\"\r\n",
+        )
+        .unwrap();
+    assert_eq!(addresses.ap.ip, Some(Ipv4Addr::new(192, 168, 4, 1)));
+    assert_eq!(addresses.ap.mac, Some(MAC));
+    assert_eq!(addresses.station.ip, Some(Ipv4Addr::UNSPECIFIED));
+    assert_eq!(
+        addresses.station.mac,
+        Some(MacAddress([0x0c, 0xd6, 0xbd, 0x0e, 0x50, 0x11]))
+    );
+    assert_eq!(addresses.station.ip6_link_local, None);
+}
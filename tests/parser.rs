@@ -0,0 +1,57 @@
+use at_protocol::command::parser::{parse_line, redact_line, Value};
+use at_protocol::command::GetWifiMode;
+use at_protocol::{Command, Error};
+
+#[test]
+fn splits_name_and_fields() {
+    let (name, fields) = parse_line("+CMD:1,\"a\\,b\",(2,(3,4)),,x").unwrap();
+    assert_eq!(name, "+CMD");
+    assert_eq!(fields.len(), 5);
+    assert_eq!(fields.values()[1], Value::Str("a,b".into()));
+    assert_eq!(fields.values()[3], Value::Empty);
+    assert_eq!(fields.values()[4], Value::Raw("x"));
+    let list = fields.list(2, "list").unwrap();
+    assert_eq!(list.get::<u8>(0, "first").unwrap(), 2);
+    assert_eq!(
+        list.list(1, "inner").unwrap().get::<u8>(1, "last").unwrap(),
+        4
+    );
+}
+
+#[test]
+fn rejects_unclosed_list() {
+    assert!(parse_line("+CMD:(1,2").is_err());
+    assert!(parse_line("+CMD:\"open").is_err());
+}
+
+#[test]
+fn rejects_deeply_nested_lists() {
+    let nested = format!("+CMD:{}1{}", "(".repeat(8), ")".repeat(8));
+    assert!(parse_line(&nested).is_ok());
+    let nested = format!("+CMD:{}1{}", "(".repeat(9), ")".repeat(9));
+    assert!(parse_line(&nested).is_err());
+
+    let response = format!("AT+CWMODE?\r\n+CWMODE:{}\r\n", "(".repeat(20_000));
+    assert!(matches!(
+        GetWifiMode.decode(response.as_bytes()),
+        Err(Error::Parse(_))
+    ));
+}
+
+fn redact(line: &str, redacted: &[usize]) -> String {
+    let mut output = String::new();
+    redact_line(line, "+CMD", redacted, &mut output).unwrap();
+    output
+}
+
+#[test]
+fn redacts_fields() {
+    assert_eq!(
+        redact("+CMD:\"a\\\"b\",(1,2),x", &[0, 1]),
+        "+CMD:\"***\",\"***\",x"
+    );
+    assert_eq!(redact("+CMD:1,\"secret\"", &[1]), "+CMD:1,\"***\"");
+    assert_eq!(redact("+OTHER:\"secret\"", &[0]), "+OTHER:\"secret\"");
+    // The rest of a line that cannot be parsed is replaced too
+    assert_eq!(redact("+CMD:1,\"unclosed", &[0]), "+CMD:\"***\",***");
+}
@@ -0,0 +1,47 @@
+use at_protocol::command::{AtString, ConnectToAp, GetConnectedAp, ListAp};
+use at_protocol::Command;
+
+mod common;
+
+use common::encode;
+
+#[test]
+fn escapes_special_characters() {
+    assert_eq!(AtString("plain").to_string(), r#""plain""#);
+    assert_eq!(AtString(r#"say "hi""#).to_string(), r#""say \"hi\"""#);
+    assert_eq!(AtString("a,b").to_string(), r#""a\,b""#);
+    assert_eq!(AtString(r"back\slash").to_string(), r#""back\\slash""#);
+    assert_eq!(AtString("tab\there").to_string(), "\"tab\there\"");
+    assert_eq!(AtString("café ☕").to_string(), "\"café ☕\"");
+}
+
+#[test]
+fn connect_to_ap_quotes_arguments() {
+    let command = ConnectToAp::new(r#"My "AP", \home"#, "pässwörd,1");
+    assert_eq!(
+        encode(&command),
+        "AT+CWJAP=\"My \\\"AP\\\"\\, \\\\home\",\"pässwörd\\,1\"\r\n"
+    );
+}
+
+#[test]
+fn list_ap_unescapes_ssid() {
+    let response = b"AT+CWLAP\r\n\
+        +CWLAP:(3,\"My \\\"AP\\\"\\, \\\\home\",-50,\"0c:d6:bd:0e:50:10\",6)\r\n\
+        +CWLAP:(0,\"caf\xc3\xa9\",-70,\"0c:d6:bd:0e:50:11\",11)";
+    let aps = ListAp.decode(response).unwrap();
+    assert_eq!(aps.len(), 2);
+    assert_eq!(aps[0].ssid, r#"My "AP", \home"#);
+    assert_eq!(aps[0].rssi, -50);
+    assert_eq!(aps[0].channel, 6);
+    assert_eq!(aps[1].ssid, "café");
+}
+
+#[test]
+fn get_connected_ap_unescapes_ssid() {
+    let response = b"AT+CWJAP?\r\n+CWJAP:\"a\\,b\\\"c\",\"0c:d6:bd:0e:50:10\",8,-49,0,0,0,0";
+    assert_eq!(
+        GetConnectedAp.decode(response).unwrap().unwrap().ssid,
+        "a,b\"c"
+    );
+}
@@ -0,0 +1,110 @@
+use at_protocol::command::{
+    Cipher, FilteredListAp, ListAp, Phy, ScanFields, ScanType, SetScanOptions, ECN,
+};
+use at_protocol::Command;
+
+mod common;
+
+use common::encode;
+
+#[test]
+fn encodes_scan_options() {
+    let options = SetScanOptions {
+        sort_by_rssi: true,
+        fields: ScanFields::NONE,
+        min_rssi: None,
+        auth_modes: None,
+    };
+    assert_eq!(encode(&options), "AT+CWLAPOPT=1,31\r\n");
+
+    let options = SetScanOptions {
+        sort_by_rssi: false,
+        fields: ScanFields {
+            wps: true,
+            ..ScanFields::NONE
+        },
+        min_rssi: Some(-70),
+        auth_modes: Some(&[ECN::WPA2_PSK, ECN::WPA_WPA2_PSK]),
+    };
+    assert_eq!(encode(&options), "AT+CWLAPOPT=0,1055,-70,24\r\n");
+    assert_eq!(
+        encode(&SetScanOptions {
+            fields: ScanFields::ALL,
+            ..options
+        }),
+        "AT+CWLAPOPT=0,2047,-70,24\r\n"
+    );
+}
+
+#[test]
+fn encodes_filters() {
+    assert_eq!(encode(&FilteredListAp::default()), "AT+CWLAP\r\n");
+    assert_eq!(
+        encode(&FilteredListAp {
+            ssid: Some("home"),
+            ..FilteredListAp::default()
+        }),
+        "AT+CWLAP=\"home\"\r\n"
+    );
+    assert_eq!(
+        encode(&FilteredListAp {
+            channel: Some(6),
+            scan_type: Some(ScanType::Passive),
+            scan_time_max: Some(300),
+            ..FilteredListAp::default()
+        }),
+        "AT+CWLAP=,,6,1,,300\r\n"
+    );
+}
+
+#[test]
+fn decodes_all_fields() {
+    let access_points = ListAp
+        .decode(b"+CWLAP:(3,\"home\",-50,\"0c:d6:bd:0e:50:10\",6,-1,-1,4,4,7,1)\r\n")
+        .unwrap();
+    let ap = &access_points[0];
+    assert_eq!(ap.frequency_offset, Some(-1));
+    assert_eq!(ap.frequency_calibration, Some(-1));
+    assert_eq!(ap.pairwise_cipher, Some(Cipher::Ccmp));
+    assert_eq!(ap.group_cipher, Some(Cipher::Ccmp));
+    assert_eq!(
+        ap.phy,
+        Some(Phy {
+            b: true,
+            g: true,
+            n: true
+        })
+    );
+    assert_eq!(ap.wps, Some(true));
+}
+
+#[test]
+fn decodes_fields_of_older_firmware() {
+    let access_points = ListAp
+        .decode(b"+CWLAP:(3,\"home\",-50,\"0c:d6:bd:0e:50:10\",6,12,0)\r\n")
+        .unwrap();
+    let ap = &access_points[0];
+    assert_eq!(ap.frequency_offset, Some(12));
+    assert_eq!(ap.frequency_calibration, Some(0));
+    assert_eq!(ap.pairwise_cipher, None);
+    assert_eq!(ap.wps, None);
+}
+
+#[test]
+fn decodes_selected_fields() {
+    let command = FilteredListAp {
+        fields: ScanFields {
+            group_cipher: true,
+            wps: true,
+            ..ScanFields::NONE
+        },
+        ..FilteredListAp::default()
+    };
+    let access_points = command
+        .decode(b"+CWLAP:(3,\"home\",-50,\"0c:d6:bd:0e:50:10\",6,3,0)\r\n")
+        .unwrap();
+    let ap = &access_points[0];
+    assert_eq!(ap.frequency_offset, None);
+    assert_eq!(ap.group_cipher, Some(Cipher::Tkip));
+    assert_eq!(ap.wps, Some(false));
+}
@@ -0,0 +1,76 @@
+use at_protocol::command::{
+    GetSoftAp, KickStation, ListStations, MacAddress, Persistence, SetSoftAp, Station, ECN,
+};
+use at_protocol::Command;
+use std::net::Ipv4Addr;
+
+mod common;
+
+use common::{encode, encode_redacted};
+
+#[test]
+fn decodes_soft_ap_config() {
+    let config = GetSoftAp
+        .decode(b"AT+CWSAP?\r\n+CWSAP:\"setup\",\"password\",5,3,4,1\r\n")
+        .unwrap();
+    assert_eq!(config.ssid, "setup");
+    assert_eq!(config.password, "password");
+    assert_eq!(config.channel, 5);
+    assert_eq!(config.ecn, ECN::WPA2_PSK);
+    assert_eq!(config.max_connections, Some(4));
+    assert_eq!(config.hidden, Some(true));
+
+    let config = GetSoftAp.decode(b"+CWSAP:\"setup\",\"\",1,0\r\n").unwrap();
+    assert_eq!(config.ecn, ECN::Open);
+    assert_eq!(config.max_connections, None);
+    assert_eq!(config.hidden, None);
+}
+
+#[test]
+fn encodes_soft_ap_config() {
+    let command = SetSoftAp {
+        ssid: "setup",
+        password: "password",
+        channel: 5,
+        ecn: ECN::WPA2_PSK,
+        max_connections: None,
+        hidden: Some(true),
+        persistence: Persistence::Current,
+    };
+    let (output, redacted) = (encode(&command), encode_redacted(&command));
+    assert_eq!(output, "AT+CWSAP_CUR=\"setup\",\"password\",5,3,,1\r\n");
+    assert_eq!(redacted, "AT+CWSAP_CUR=\"setup\",\"***\",5,3,,1\r\n");
+}
+
+#[test]
+fn decodes_stations() {
+    let station = Station {
+        ip: Ipv4Addr::new(192, 168, 4, 2),
+        mac: MacAddress([0x0c, 0xd6, 0xbd, 0x0e, 0x50, 0x10]),
+    };
+    assert_eq!(
+        ListStations
+            .decode(b"AT+CWLIF\r\n+CWLIF:\"192.168.4.2\",\"0c:d6:bd:0e:50:10\"\r\n")
+            .unwrap(),
+        vec![station]
+    );
+    assert_eq!(
+        ListStations
+            .decode(b"AT+CWLIF\r\n192.168.4.2,0c:d6:bd:0e:50:10\r\n\r\n")
+            .unwrap(),
+        vec![station]
+    );
+    assert_eq!(ListStations.decode(b"AT+CWLIF\r\n").unwrap(), vec![]);
+    assert!(ListStations.decode(b"192.168.4.2\r\n").is_err());
+}
+
+#[test]
+fn encodes_kick_station() {
+    assert_eq!(encode(&KickStation(None)), "AT+CWQIF\r\n");
+    assert_eq!(
+        encode(&KickStation(Some(MacAddress([
+            0x0c, 0xd6, 0xbd, 0x0e, 0x50, 0x10
+        ])))),
+        "AT+CWQIF=\"0c:d6:bd:0e:50:10\"\r\n"
+    );
+}
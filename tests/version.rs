@@ -0,0 +1,55 @@
+use at_protocol::command::{FirmwareFamily, GetVersion, Version};
+use at_protocol::Command;
+
+#[test]
+fn parses_esp_at_v2() {
+    let version = GetVersion
+        .decode(
+            b"AT+GMR\r\n\
+              AT version:2.2.0.0(c6fa6bf - ESP32 - Jul  2 2021 06:44:05)\r\n\
+              SDK version:v4.2.2-76-gefa6eca\r\n\
+              compile time(3a696ba):Jul  2 2021 11:54:43\r\n\
+              Bin version:2.2.0(WROOM-32)\r\n",
+        )
+        .unwrap();
+    assert_eq!(version.at_version.as_str(), "2.2.0.0");
+    assert_eq!(version.sdk_version.as_str(), "v4.2.2-76-gefa6eca");
+    assert_eq!(
+        version.compile_time.as_deref(),
+        Some("Jul  2 2021 11:54:43")
+    );
+    assert_eq!(version.bin_version, Some(Version::from("2.2.0")));
+    assert!(version.extra.is_empty());
+    assert_eq!(version.family(), FirmwareFamily::EspAtV2);
+}
+
+#[test]
+fn parses_nonos() {
+    let version = GetVersion
+        .decode(
+            b"AT version:1.2.0.0(Jul  1 2016 20:04:45)\r\n\
+              SDK version:1.5.4.1(39cb9a32)\r\n\
+              Ai-Thinker Technology Co. Ltd.\r\n\
+              Dec  2 2016 14:21:16\r\n",
+        )
+        .unwrap();
+    assert_eq!(version.at_version.as_str(), "1.2.0.0");
+    assert_eq!(version.sdk_version.as_str(), "1.5.4.1");
+    assert_eq!(version.compile_time, None);
+    assert_eq!(version.bin_version, None);
+    assert_eq!(
+        version.extra,
+        ["Ai-Thinker Technology Co. Ltd.", "Dec  2 2016 14:21:16"]
+    );
+    assert_eq!(version.family(), FirmwareFamily::NonOs);
+}
+
+#[test]
+fn compares_versions() {
+    assert!(Version::from("1.7.4") < Version::from("2.0"));
+    assert!(Version::from("2.2.0.0") > Version::from("2.1.9"));
+    assert_eq!(Version::from("2.2"), Version::from("2.2.0.0"));
+    assert_eq!(Version::from("v4.2.2-76-gefa6eca"), Version::from("4.2.2"));
+    assert_eq!(Version::from("v4.2.2-76-gefa6eca").numbers(), [4, 2, 2]);
+    assert_eq!(Version::from("unknown").major(), None);
+}
@@ -0,0 +1,153 @@
+use at_protocol::command::{ConnectToAp, GetConnectedAp, MacAddress, Persistence, Pmf, ScanMode};
+use at_protocol::{Command, Error, JoinFailure, Outcome, Rejection, Response};
+use std::time::Duration;
+
+mod common;
+
+use common::{encode, encode_redacted};
+
+#[test]
+fn decodes_connected_ap() {
+    let ap = GetConnectedAp
+        .decode(b"AT+CWJAP?\r\n+CWJAP:\"home\",\"0c:d6:bd:0e:50:10\",8,-49,1,5,3,1,3\r\n")
+        .unwrap()
+        .unwrap();
+    assert_eq!(ap.ssid, "home");
+    assert_eq!(ap.bssid, MacAddress([0x0c, 0xd6, 0xbd, 0x0e, 0x50, 0x10]));
+    assert_eq!(ap.channel, 8);
+    assert_eq!(ap.rssi, -49);
+    assert_eq!(ap.pci_en, Some(true));
+    assert_eq!(ap.reconnect_interval, Some(5));
+    assert_eq!(ap.listen_interval, Some(3));
+    assert_eq!(ap.scan_mode, Some(ScanMode::AllChannels));
+    assert_eq!(
+        ap.pmf,
+        Some(Pmf {
+            capable: true,
+            required: true
+        })
+    );
+}
+
+#[test]
+fn decodes_connected_ap_from_older_firmware() {
+    let ap = GetConnectedAp
+        .decode(b"+CWJAP:\"home\",\"0c:d6:bd:0e:50:10\",8,-49\r\n")
+        .unwrap()
+        .unwrap();
+    assert_eq!(ap.rssi, -49);
+    assert_eq!(ap.pci_en, None);
+    assert_eq!(ap.scan_mode, None);
+    assert_eq!(ap.pmf, None);
+}
+
+#[test]
+fn decodes_no_connected_ap() {
+    assert_eq!(
+        GetConnectedAp.decode(b"AT+CWJAP?\r\nNo AP\r\n").unwrap(),
+        None
+    );
+    assert!(GetConnectedAp.decode(b"+CWJAP:\"home\"\r\n").is_err());
+}
+
+#[test]
+fn encodes_connect_options() {
+    let command = ConnectToAp::new("home", "secret");
+    let (output, redacted) = (encode(&command), encode_redacted(&command));
+    assert_eq!(output, "AT+CWJAP=\"home\",\"secret\"\r\n");
+    assert_eq!(redacted, "AT+CWJAP=\"home\",\"***\"\r\n");
+
+    let command = ConnectToAp {
+        bssid: Some(MacAddress([0x0c, 0xd6, 0xbd, 0x0e, 0x50, 0x10])),
+        scan_mode: Some(ScanMode::AllChannels),
+        jap_timeout: Some(60),
+        ..ConnectToAp::new("home", "secret")
+    };
+    let (output, redacted) = (encode(&command), encode_redacted(&command));
+    assert_eq!(
+        output,
+        "AT+CWJAP=\"home\",\"secret\",\"0c:d6:bd:0e:50:10\",,,,1,60\r\n"
+    );
+    assert_eq!(
+        redacted,
+        "AT+CWJAP=\"home\",\"***\",\"0c:d6:bd:0e:50:10\",,,,1,60\r\n"
+    );
+    assert_eq!(command.timeout(), Some(Duration::from_secs(65)));
+
+    let command = ConnectToAp {
+        persistence: Persistence::Current,
+        ..ConnectToAp::new("home", "secret")
+    };
+    assert_eq!(encode(&command), "AT+CWJAP_CUR=\"home\",\"secret\"\r\n");
+
+    let command = ConnectToAp {
+        pmf: Some(Pmf {
+            capable: true,
+            required: false,
+        }),
+        ..ConnectToAp::new("home", "secret")
+    };
+    assert_eq!(encode(&command), "AT+CWJAP=\"home\",\"secret\",,,,,,,1\r\n");
+}
+
+#[test]
+fn reports_connect_failure_reason() {
+    let response = Response {
+        body: b"AT+CWJAP=\"home\",\"wrong\"\r\n+CWJAP:2\r\n",
+        outcome: Outcome::Fail,
+    };
+    assert!(matches!(
+        ConnectToAp::new("home", "wrong").decode_response(&response),
+        Err(Error::ConnectFailed(JoinFailure::WrongPassword))
+    ));
+}
+
+#[test]
+fn reports_connect_failure_reason_with_persistence_suffix() {
+    let command = ConnectToAp {
+        persistence: Persistence::Current,
+        ..ConnectToAp::new("home", "wrong")
+    };
+    let response = Response {
+        body: b"AT+CWJAP_CUR=\"home\",\"wrong\"\r\n+CWJAP_CUR:3\r\n",
+        outcome: Outcome::Fail,
+    };
+    assert!(matches!(
+        command.decode_response(&response),
+        Err(Error::ConnectFailed(JoinFailure::ApNotFound))
+    ));
+
+    let command = ConnectToAp {
+        persistence: Persistence::Saved,
+        ..command
+    };
+    let response = Response {
+        body: b"AT+CWJAP_DEF=\"home\",\"wrong\"\r\n+CWJAP_DEF:1\r\n",
+        outcome: Outcome::Fail,
+    };
+    assert!(matches!(
+        command.decode_response(&response),
+        Err(Error::ConnectFailed(JoinFailure::Timeout))
+    ));
+}
+
+#[test]
+fn reports_connect_failure_reason_before_error() {
+    let response = Response {
+        body: b"AT+CWJAP=\"home\",\"wrong\"\r\n+CWJAP:2\r\n",
+        outcome: Outcome::Error(None),
+    };
+    assert!(matches!(
+        ConnectToAp::new("home", "wrong").decode_response(&response),
+        Err(Error::ConnectFailed(JoinFailure::WrongPassword))
+    ));
+
+    let response = Response {
+        body: b"AT+CWJAP=\"home\",\"wrong\"\r\n",
+        outcome: Outcome::Error(None),
+    };
+    assert!(matches!(
+        ConnectToAp::new("home", "wrong").decode_response(&response),
+        Err(Error::Rejected(Rejection::Error(None)))
+    ));
+}